@@ -0,0 +1,77 @@
+use crate::{point3d::Point3D, ray::Ray};
+
+// Axis-aligned bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Aabb {
+    pub min: Point3D,
+    pub max: Point3D,
+}
+
+impl Aabb {
+    // corners can be given in any order
+    pub fn new(a: Point3D, b: Point3D) -> Self {
+        Self {
+            min: a.min(&b),
+            max: a.max(&b),
+        }
+    }
+    pub fn empty() -> Self {
+        Self {
+            min: Point3D::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point3D::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.min(&other.min),
+            max: self.max.max(&other.max),
+        }
+    }
+    pub fn grow(&self, point: &Point3D) -> Aabb {
+        Aabb {
+            min: self.min.min(point),
+            max: self.max.max(point),
+        }
+    }
+    pub fn centroid(&self) -> Point3D {
+        (self.min + self.max) * 0.5
+    }
+    pub fn surface_area(&self) -> f64 {
+        let d = self.max - self.min;
+        if d.x() < 0. || d.y() < 0. || d.z() < 0. {
+            return 0.;
+        }
+        2. * (d.x() * d.y() + d.y() * d.z() + d.z() * d.x())
+    }
+    pub fn longest_axis(&self) -> usize {
+        let d = self.max - self.min;
+        if d.x() >= d.y() && d.x() >= d.z() {
+            0
+        } else if d.y() >= d.z() {
+            1
+        } else {
+            2
+        }
+    }
+    // slab test, `inv_direction` is 1 / ray.direction per component
+    pub fn hit(&self, ray: &Ray, inv_direction: &Point3D, t_min: f64, t_max: f64) -> bool {
+        let mut t_min = t_min;
+        let mut t_max = t_max;
+        for axis in 0..3 {
+            let t0 = (self.min.axis(axis) - ray.origin.axis(axis)) * inv_direction.axis(axis);
+            let t1 = (self.max.axis(axis) - ray.origin.axis(axis)) * inv_direction.axis(axis);
+            let (t0, t1) = if t0 > t1 { (t1, t0) } else { (t0, t1) };
+            // written so that NaN (ray in the slab plane) keeps the old bounds
+            if t0 > t_min {
+                t_min = t0
+            }
+            if t1 < t_max {
+                t_max = t1
+            }
+            if t_max < t_min {
+                return false;
+            }
+        }
+        true
+    }
+}
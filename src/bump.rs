@@ -0,0 +1,197 @@
+use crate::{objects::Intersection, point3d::Point3D, texture::Texture};
+use std::sync::Arc;
+
+// Small surface detail that changes the shading normal but not the shape,
+// e.g. bricks, scratches or a rough plaster wall
+#[derive(Clone)]
+pub enum Bump {
+    // tangent space normals stored as colors: red along u, green along v,
+    // blue straight out of the surface
+    NormalMap(Arc<dyn Texture>),
+    // the surface seems raised by the brightness of the texture times `strength`,
+    // which is in scene units
+    Height {
+        texture: Arc<dyn Texture>,
+        strength: f64,
+    },
+}
+
+impl Bump {
+    // replaces the normal of the hit, it keeps facing the same side as before
+    pub fn apply(&self, intersection: &mut Intersection) {
+        let outward = if intersection.front_face {
+            intersection.normal
+        } else {
+            intersection.normal * -1.
+        };
+        let perturbed = match self {
+            Bump::NormalMap(texture) => normal_map(texture.as_ref(), intersection, &outward),
+            Bump::Height { texture, strength } => {
+                height_map(texture.as_ref(), *strength, intersection, &outward)
+            }
+        };
+        let Some(perturbed) = perturbed else {
+            // surfaces without a usable tangent keep their normal
+            return;
+        };
+        intersection.normal = if intersection.front_face {
+            perturbed
+        } else {
+            perturbed * -1.
+        };
+    }
+}
+
+fn normal_map(
+    texture: &dyn Texture,
+    intersection: &Intersection,
+    normal: &Point3D,
+) -> Option<Point3D> {
+    // tangent along u, made perpendicular to the normal
+    let tangent = intersection.dpdu - *normal * normal.dot(&intersection.dpdu);
+    if tangent.near_zero() {
+        return None;
+    }
+    let tangent = tangent.unit_vector();
+    let bitangent = normal.cross(&tangent);
+    let [x, y, z] = texture
+        .value(intersection.uv, &intersection.point)
+        .components()
+        .map(|c| 2. * c - 1.);
+    let perturbed = tangent * x + bitangent * y + *normal * z;
+    if perturbed.near_zero() {
+        return None;
+    }
+    Some(perturbed.unit_vector())
+}
+
+// Moves the surface along the normal by the height and takes the normal of the
+// moved surface. The slopes come from finite differences a short step along u and v.
+fn height_map(
+    texture: &dyn Texture,
+    strength: f64,
+    intersection: &Intersection,
+    normal: &Point3D,
+) -> Option<Point3D> {
+    const STEP: f64 = 1e-3;
+    let (dpdu, dpdv) = (intersection.dpdu, intersection.dpdv);
+    if dpdu.near_zero() || dpdv.near_zero() {
+        return None;
+    }
+    let (u, v) = intersection.uv;
+    let point = intersection.point;
+    let height = |uv: (f64, f64), p: Point3D| texture.value(uv, &p).luminance() * strength;
+    let base = height((u, v), point);
+    // steps of the same length in the scene, no matter how large the uv range is
+    let du = STEP / dpdu.length();
+    let dv = STEP / dpdv.length();
+    let slope_u = (height((u + du, v), point + dpdu * du) - base) / du;
+    let slope_v = (height((u, v + dv), point + dpdv * dv) - base) / dv;
+    let bumped_dpdu = dpdu + *normal * slope_u;
+    let bumped_dpdv = dpdv + *normal * slope_v;
+    let perturbed = bumped_dpdu.cross(&bumped_dpdv);
+    if perturbed.near_zero() {
+        return None;
+    }
+    let perturbed = perturbed.unit_vector();
+    // dpdu x dpdv may point inwards on mirrored surfaces
+    if perturbed.dot(normal) < 0. {
+        Some(perturbed * -1.)
+    } else {
+        Some(perturbed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, texture::SolidColor};
+
+    // gray level equal to u
+    struct Ramp;
+
+    impl Texture for Ramp {
+        fn value(&self, (u, _): (f64, f64), _point: &Point3D) -> Color {
+            Color::new(u, u, u)
+        }
+    }
+
+    // flat hit on the xy plane, u along x and v along y, seen from +z
+    fn flat_hit<'a>() -> Intersection<'a> {
+        let mut hit = Intersection::new();
+        hit.normal = Point3D::new(0., 0., 1.);
+        hit.dpdu = Point3D::new(1., 0., 0.);
+        hit.dpdv = Point3D::new(0., 1., 0.);
+        hit.uv = (0.5, 0.5);
+        hit
+    }
+
+    fn assert_close(actual: Point3D, expected: Point3D) {
+        assert!((actual - expected).length() < 1e-6, "{actual:?} != {expected:?}");
+    }
+
+    fn normal_map(color: Color) -> Bump {
+        Bump::NormalMap(Arc::new(SolidColor::new(color)))
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_the_normal() {
+        let mut hit = flat_hit();
+        normal_map(Color::new(0.5, 0.5, 1.)).apply(&mut hit);
+        assert_close(hit.normal, Point3D::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn normal_map_tilts_along_the_tangents() {
+        let mut hit = flat_hit();
+        normal_map(Color::new(1., 0.5, 0.5)).apply(&mut hit);
+        assert_close(hit.normal, Point3D::new(1., 0., 0.));
+        let mut hit = flat_hit();
+        normal_map(Color::new(0.5, 1., 0.5)).apply(&mut hit);
+        assert_close(hit.normal, Point3D::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn normal_map_uses_the_tangent_perpendicular_to_the_normal() {
+        let mut hit = flat_hit();
+        // a tangent leaning out of the surface must not tilt the result
+        hit.dpdu = Point3D::new(1., 0., 1.);
+        normal_map(Color::new(0.5, 0.5, 1.)).apply(&mut hit);
+        assert_close(hit.normal, Point3D::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn height_map_leans_away_from_the_slope() {
+        let strength = 0.5;
+        let mut hit = flat_hit();
+        Bump::Height { texture: Arc::new(Ramp), strength }.apply(&mut hit);
+        // the surface rises by `strength` per unit along u
+        assert_close(hit.normal, Point3D::new(-strength, 0., 1.).unit_vector());
+    }
+
+    #[test]
+    fn constant_height_keeps_the_normal() {
+        let mut hit = flat_hit();
+        let texture = Arc::new(SolidColor::new(Color::new(0.3, 0.3, 0.3)));
+        Bump::Height { texture, strength: 2. }.apply(&mut hit);
+        assert_close(hit.normal, Point3D::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn back_faces_stay_on_their_side() {
+        let mut hit = flat_hit();
+        hit.front_face = false;
+        hit.normal = Point3D::new(0., 0., -1.);
+        normal_map(Color::new(1., 0.5, 1.)).apply(&mut hit);
+        assert!(hit.normal.z() < 0.);
+        assert_close(hit.normal, Point3D::new(-1., 0., -1.).unit_vector());
+    }
+
+    #[test]
+    fn missing_tangents_keep_the_normal() {
+        let mut hit = flat_hit();
+        hit.dpdu = Point3D::new(0., 0., 0.);
+        normal_map(Color::new(1., 0.5, 0.5)).apply(&mut hit);
+        assert_close(hit.normal, Point3D::new(0., 0., 1.));
+    }
+}
@@ -1,4 +1,92 @@
-use crate::{point3d::Point3D, ray::Ray};
+use crate::{point3d::Point3D, ray::Ray, sampler::Sampler};
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Projection {
+    Perspective,
+    // parallel rays, `height` is the height of the view in scene units
+    Orthographic { height: f64 },
+    // equidistant circular fisheye, the field of view spans the image circle
+    Fisheye,
+    // full 360° x 180° panorama, meant for images twice as wide as high
+    Equirectangular,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum FieldOfView {
+    // full angle between the top and bottom edge of the image, in radians
+    Vertical(f64),
+    // full angle between the left and right edge of the image, in radians
+    Horizontal(f64),
+    // lens focal length and sensor size in millimeters, the image is
+    // the largest crop of the sensor with the image's aspect ratio
+    Physical {
+        focal_length: f64,
+        sensor_width: f64,
+        sensor_height: f64,
+    },
+}
+
+impl FieldOfView {
+    // half width and half height of the image at distance 1 in front of the camera
+    fn half_extent(&self, aspect_ratio: f64) -> (f64, f64) {
+        match *self {
+            FieldOfView::Vertical(angle) => {
+                let half_height = (angle / 2.).tan();
+                (half_height * aspect_ratio, half_height)
+            }
+            FieldOfView::Horizontal(angle) => {
+                let half_width = (angle / 2.).tan();
+                (half_width, half_width / aspect_ratio)
+            }
+            FieldOfView::Physical {
+                focal_length,
+                sensor_width,
+                sensor_height,
+            } => {
+                if aspect_ratio >= sensor_width / sensor_height {
+                    let half_width = sensor_width / 2. / focal_length;
+                    (half_width, half_width / aspect_ratio)
+                } else {
+                    let half_height = sensor_height / 2. / focal_length;
+                    (half_height * aspect_ratio, half_height)
+                }
+            }
+        }
+    }
+    // vertical angle of an equidistant fisheye, where the angle grows linearly across the image
+    fn fisheye_angle(&self, aspect_ratio: f64) -> f64 {
+        match *self {
+            FieldOfView::Vertical(angle) => angle,
+            FieldOfView::Horizontal(angle) => angle / aspect_ratio,
+            // an equidistant lens maps the angle θ to f * θ on the sensor
+            FieldOfView::Physical { .. } => 2. * self.half_extent(aspect_ratio).1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Focus {
+    // focus on the camera's target point
+    Auto,
+    Distance(f64),
+}
+
+// Thin lens, an aperture of 0 is a pinhole camera where everything is sharp
+#[derive(Debug, Clone, Copy)]
+pub struct Lens {
+    pub aperture: f64,
+    pub focus: Focus,
+}
+
+impl Lens {
+    pub fn pinhole() -> Self {
+        Self {
+            aperture: 0.,
+            focus: Focus::Auto,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct Camera {
@@ -8,6 +96,11 @@ pub struct Camera {
     pub right: Point3D,
     pub width: f64,
     pub height: f64,
+    pub lens_radius: f64,
+    pub focus_distance: f64,
+    pub fisheye_angle: f64,
+    pub aspect_ratio: f64,
+    pub projection: Projection,
 }
 
 impl Camera {
@@ -15,14 +108,19 @@ impl Camera {
         origin: Point3D,
         target: Point3D,
         up_guide: Point3D,
-        fov: f64,
+        fov: FieldOfView,
         aspect_ratio: f64,
+        lens: Lens,
+        projection: Projection,
     ) -> Self {
         let forward = (target - origin).unit_vector();
         let right = forward.cross(&up_guide).unit_vector();
         let up = right.cross(&forward);
-        let height = fov.tan();
-        let width = height * aspect_ratio;
+        let (width, height) = fov.half_extent(aspect_ratio);
+        let focus_distance = match lens.focus {
+            Focus::Auto => (target - origin).length(),
+            Focus::Distance(distance) => distance,
+        };
 
         Self {
             origin,
@@ -31,10 +129,58 @@ impl Camera {
             right,
             width,
             height,
+            lens_radius: lens.aperture / 2.,
+            focus_distance,
+            fisheye_angle: fov.fisheye_angle(aspect_ratio),
+            aspect_ratio,
+            projection,
         }
     }
-    pub fn make_ray(&self, u: f64, v: f64) -> Ray {
+    // u and v go from -1 to 1 across the image, None where the projection doesn't cover it
+    // `sampler` picks the point on the lens
+    pub fn make_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Option<Ray> {
+        match self.projection {
+            Projection::Perspective => Some(self.perspective_ray(u, v, sampler)),
+            Projection::Orthographic { height } => {
+                let offset = self.right * (u * height / 2. * self.aspect_ratio)
+                    + self.up * (v * height / 2.);
+                Some(Ray::new(self.origin + offset, self.forward))
+            }
+            Projection::Fisheye => {
+                let x = u * self.aspect_ratio;
+                let radius = (x * x + v * v).sqrt();
+                if radius > 1. {
+                    return None;
+                }
+                // angle from the view axis grows linearly with the distance from the center
+                let theta = radius * self.fisheye_angle / 2.;
+                let sideways = if radius > 0. {
+                    (self.right * x + self.up * v) / radius
+                } else {
+                    Point3D::new(0., 0., 0.)
+                };
+                let direction = self.forward * theta.cos() + sideways * theta.sin();
+                Some(Ray::new(self.origin, direction))
+            }
+            Projection::Equirectangular => {
+                let longitude = u * PI;
+                let latitude = v * PI / 2.;
+                let direction = self.forward * (latitude.cos() * longitude.cos())
+                    + self.right * (latitude.cos() * longitude.sin())
+                    + self.up * latitude.sin();
+                Some(Ray::new(self.origin, direction))
+            }
+        }
+    }
+    fn perspective_ray(&self, u: f64, v: f64, sampler: &mut dyn Sampler) -> Ray {
         let direction = self.forward + self.right * self.width * u + self.up * self.height * v;
-        Ray::new(self.origin, direction.unit_vector())
+        if self.lens_radius <= 0. {
+            return Ray::new(self.origin, direction.unit_vector());
+        }
+        // every ray through the lens meets the pinhole ray on the plane of focus
+        let focus_point = self.origin + direction * self.focus_distance;
+        let disk = Point3D::in_unit_disk(sampler.next_2d()) * self.lens_radius;
+        let origin = self.origin + self.right * disk.x() + self.up * disk.y();
+        Ray::new(origin, (focus_point - origin).unit_vector())
     }
 }
@@ -7,7 +7,7 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn new(r: f64, g: f64, b: f64) -> Self {
+    pub const fn new(r: f64, g: f64, b: f64) -> Self {
         Self { r, g, b }
     }
     pub fn red() -> Self {
@@ -45,25 +45,13 @@ impl Color {
             b: 0.1,
         }
     }
-    pub fn write(&mut self, samples_per_pixel: i32) {
-        let scale = 1.0 / samples_per_pixel as f64;
-        self.flatten_by_scale(scale);
-        self.clamp(0.0, 0.999);
-
-        let red = (255.999 * self.r) as i32;
-        let green = (255.999 * self.g) as i32;
-        let blue = (255.999 * self.b) as i32;
-        println!("{red} {green} {blue}");
-    }
-    fn flatten_by_scale(&mut self, scale: f64) {
-        self.r = (self.r * scale).sqrt();
-        self.g = (self.g * scale).sqrt();
-        self.b = (self.b * scale).sqrt();
+    // linear red, green and blue
+    pub fn components(&self) -> [f64; 3] {
+        [self.r, self.g, self.b]
     }
-    fn clamp(&mut self, min: f64, max: f64) {
-        self.r = clamp(self.r, min, max);
-        self.g = clamp(self.g, min, max);
-        self.b = clamp(self.b, min, max);
+    // brightness as the eye sees it, Rec. 709 weights
+    pub fn luminance(&self) -> f64 {
+        0.2126 * self.r + 0.7152 * self.g + 0.0722 * self.b
     }
 }
 
@@ -99,13 +87,3 @@ impl Mul<Color> for Color {
         }
     }
 }
-
-fn clamp(x: f64, min: f64, max: f64) -> f64 {
-    if x < min {
-        return min;
-    }
-    if x > max {
-        return max;
-    }
-    x
-}
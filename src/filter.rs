@@ -0,0 +1,92 @@
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterKind {
+    Box,
+    Tent,
+    Gaussian,
+    Mitchell,
+    Lanczos,
+}
+
+impl FilterKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "box" => Some(FilterKind::Box),
+            "tent" => Some(FilterKind::Tent),
+            "gaussian" => Some(FilterKind::Gaussian),
+            "mitchell" => Some(FilterKind::Mitchell),
+            "lanczos" => Some(FilterKind::Lanczos),
+            _ => None,
+        }
+    }
+    // radius in pixels used when the scene doesn't give one
+    pub fn default_radius(&self) -> f64 {
+        match self {
+            FilterKind::Box => 0.5,
+            FilterKind::Tent => 1.,
+            FilterKind::Gaussian => 1.5,
+            FilterKind::Mitchell => 2.,
+            FilterKind::Lanczos => 3.,
+        }
+    }
+}
+
+// Pixel reconstruction filter. Every sample adds to all pixels whose
+// center is closer than `radius` pixels, weighted by the filter.
+#[derive(Debug, Clone, Copy)]
+pub struct Filter {
+    pub kind: FilterKind,
+    pub radius: f64,
+}
+
+impl Filter {
+    pub fn new(kind: FilterKind, radius: f64) -> Self {
+        Self { kind, radius }
+    }
+    // a box of exactly one pixel, the same as averaging the samples of every pixel
+    pub fn pixel_box() -> Self {
+        Filter::new(FilterKind::Box, 0.5)
+    }
+    // offsets from the pixel center in pixels, the filters are separable
+    pub fn weight(&self, dx: f64, dy: f64) -> f64 {
+        self.weight_1d(dx) * self.weight_1d(dy)
+    }
+    fn weight_1d(&self, x: f64) -> f64 {
+        let x = x.abs();
+        if x >= self.radius {
+            return 0.;
+        }
+        match self.kind {
+            FilterKind::Box => 1.,
+            FilterKind::Tent => self.radius - x,
+            FilterKind::Gaussian => {
+                // standard deviation of half a pixel, shifted down to reach zero at the radius
+                const ALPHA: f64 = 2.;
+                (-ALPHA * x * x).exp() - (-ALPHA * self.radius * self.radius).exp()
+            }
+            FilterKind::Mitchell => mitchell(2. * x / self.radius),
+            FilterKind::Lanczos => sinc(x) * sinc(x / self.radius),
+        }
+    }
+}
+
+// Mitchell–Netravali cubic with B = C = 1/3, defined on [0, 2)
+fn mitchell(x: f64) -> f64 {
+    const B: f64 = 1. / 3.;
+    const C: f64 = 1. / 3.;
+    let value = if x < 1. {
+        (12. - 9. * B - 6. * C) * x * x * x + (-18. + 12. * B + 6. * C) * x * x + (6. - 2. * B)
+    } else {
+        (-B - 6. * C) * x * x * x + (6. * B + 30. * C) * x * x + (-12. * B - 48. * C) * x
+            + (8. * B + 24. * C)
+    };
+    value / 6.
+}
+
+fn sinc(x: f64) -> f64 {
+    if x < 1e-5 {
+        return 1.;
+    }
+    (PI * x).sin() / (PI * x)
+}
@@ -1,34 +1,171 @@
-use crate::color::Color;
+mod hdr;
+mod pfm;
+pub mod png;
+pub mod ppm;
+mod zlib;
+
+use crate::{color::Color, filter::Filter, tonemap::ToneMap};
+use std::{fs, io, path::Path};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    Ppm,
+    Png,
+    // high dynamic range formats keep the linear radiance without gamma or clamping
+    Hdr,
+    Pfm,
+}
+
+impl OutputFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(OutputFormat::Ppm),
+            "png" => Some(OutputFormat::Png),
+            "hdr" => Some(OutputFormat::Hdr),
+            "pfm" => Some(OutputFormat::Pfm),
+            _ => None,
+        }
+    }
+}
 
 pub struct Image {
     pub width: usize,
     pub height: usize,
     pub samples_per_pixel: i32,
+    pub filter: Filter,
+    // only used for 8-bit output, the HDR formats store the radiance as it is
+    pub tone_map: ToneMap,
+    // weighted sum of the samples and the sum of their weights for every pixel
     pub pixels: Vec<Color>,
+    pub weights: Vec<f64>,
+}
+
+// Accumulation buffer for part of an image. Samples near the edge also
+// reach pixels outside of the part, so it reaches `filter.radius` further.
+pub struct Film {
+    x0: usize,
+    y0: usize,
+    width: usize,
+    height: usize,
+    filter: Filter,
+    pixels: Vec<Color>,
+    weights: Vec<f64>,
 }
 
 impl Image {
-    pub fn new(width: usize, height: usize, samples_per_pixel:i32) -> Self {
+    pub fn new(
+        width: usize,
+        height: usize,
+        samples_per_pixel: i32,
+        filter: Filter,
+        tone_map: ToneMap,
+    ) -> Self {
         Self {
             width,
             height,
-            pixels: vec![Color::new(0., 0., 0.); width * height],
             samples_per_pixel,
+            filter,
+            tone_map,
+            pixels: vec![Color::black(); width * height],
+            weights: vec![0.; width * height],
+        }
+    }
+    // film for samples taken in pixels [x0, x1) x [y0, y1)
+    pub fn film(&self, x0: usize, y0: usize, x1: usize, y1: usize) -> Film {
+        let margin = self.filter.radius.ceil() as usize;
+        let fx0 = x0.saturating_sub(margin);
+        let fy0 = y0.saturating_sub(margin);
+        let width = (x1 + margin).min(self.width) - fx0;
+        let height = (y1 + margin).min(self.height) - fy0;
+        Film {
+            x0: fx0,
+            y0: fy0,
+            width,
+            height,
+            filter: self.filter,
+            pixels: vec![Color::black(); width * height],
+            weights: vec![0.; width * height],
         }
     }
-    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) {
+    pub fn merge(&mut self, film: &Film) {
+        for y in 0..film.height {
+            for x in 0..film.width {
+                let i = (film.x0 + x) + (film.y0 + y) * self.width;
+                let j = x + y * film.width;
+                self.pixels[i] = self.pixels[i] + film.pixels[j];
+                self.weights[i] += film.weights[j];
+            }
+        }
+    }
+    // filtered color of a pixel
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
         let i = x + y * self.width;
-        self.pixels[i] = color;
-    }
-    pub fn save_image(&mut self) {
-        self.print_header();
-        self.pixels
-            .iter_mut()
-            .for_each(|pixel| pixel.write(self.samples_per_pixel))
-    }
-    fn print_header(&self) {
-        println!("P3");
-        println!("{} {}", self.width, self.height);
-        println! {"255"}
+        if self.weights[i] <= 0. {
+            return Color::black();
+        }
+        self.pixels[i] * (1. / self.weights[i])
+    }
+    // format is picked from the file extension
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let format = OutputFormat::from_path(path).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "unsupported output format, use .png, .ppm, .hdr or .pfm",
+            )
+        })?;
+        let bytes = match format {
+            OutputFormat::Ppm => ppm::encode(self.width, self.height, &self.to_rgb8()),
+            OutputFormat::Png => png::encode(self.width, self.height, &self.to_rgb8()),
+            OutputFormat::Hdr => hdr::encode(self.width, self.height, &self.to_linear()),
+            OutputFormat::Pfm => pfm::encode(self.width, self.height, &self.to_linear()),
+        };
+        fs::write(path, bytes)
+    }
+    // filtered radiance of every pixel, rows top to bottom
+    pub fn to_linear(&self) -> Vec<Color> {
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| self.pixel(x, y)))
+            .collect()
+    }
+    // tone mapped 8-bit sRGB, rows top to bottom
+    pub fn to_rgb8(&self) -> Vec<u8> {
+        self.to_linear()
+            .into_iter()
+            .flat_map(|pixel| self.tone_map.to_rgb8(pixel))
+            .collect()
+    }
+}
+
+// Reads a PNG or PPM file, returns width, height and 8-bit RGB rows top to bottom
+pub fn load_rgb8(path: &Path) -> io::Result<(usize, usize, Vec<u8>)> {
+    let bytes = fs::read(path)?;
+    // recognized by content, the extension may not match
+    if bytes.starts_with(b"\x89PNG") {
+        png::decode(&bytes)
+    } else {
+        ppm::decode(&bytes)
+    }
+}
+
+impl Film {
+    // `x` and `y` are the sample position in pixels, pixel (i, j) has its center at (i + 0.5, j + 0.5)
+    pub fn add_sample(&mut self, x: f64, y: f64, color: Color) {
+        let radius = self.filter.radius;
+        let first_x = ((x - 0.5 - radius).ceil().max(self.x0 as f64)) as usize;
+        let first_y = ((y - 0.5 - radius).ceil().max(self.y0 as f64)) as usize;
+        let last_x = (x - 0.5 + radius).floor().min((self.x0 + self.width) as f64 - 1.);
+        let last_y = (y - 0.5 + radius).floor().min((self.y0 + self.height) as f64 - 1.);
+        for py in first_y..=last_y as usize {
+            for px in first_x..=last_x as usize {
+                let weight = self.filter.weight(px as f64 + 0.5 - x, py as f64 + 0.5 - y);
+                if weight == 0. {
+                    continue;
+                }
+                let i = (px - self.x0) + (py - self.y0) * self.width;
+                self.pixels[i] = self.pixels[i] + color * weight;
+                self.weights[i] += weight;
+            }
+        }
     }
 }
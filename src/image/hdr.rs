@@ -0,0 +1,92 @@
+use crate::color::Color;
+
+// runs shorter than this are cheaper to store as literal bytes
+const MIN_RUN: usize = 4;
+
+// Radiance RGBE image with run length encoded scanlines, rows stored top to bottom
+pub fn encode(width: usize, height: usize, pixels: &[Color]) -> Vec<u8> {
+    let mut out = format!("#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y {height} +X {width}\n").into_bytes();
+    for row in pixels.chunks(width).take(height) {
+        let rgbe: Vec<[u8; 4]> = row.iter().map(|pixel| to_rgbe(pixel.components())).collect();
+        // readers only understand run length encoding for these widths
+        if !(8..=0x7fff).contains(&width) {
+            out.extend(rgbe.iter().flatten());
+            continue;
+        }
+        out.extend_from_slice(&[2, 2, (width >> 8) as u8, width as u8]);
+        for channel in 0..4 {
+            let bytes: Vec<u8> = rgbe.iter().map(|p| p[channel]).collect();
+            encode_channel(&mut out, &bytes);
+        }
+    }
+    out
+}
+
+// shared exponent for the three channels, the mantissas keep 8 bits each.
+// NaN becomes black, infinity and anything too bright for the exponent byte the brightest value.
+fn to_rgbe(rgb: [f64; 3]) -> [u8; 4] {
+    let [r, g, b] = rgb.map(|c| c.max(0.));
+    let brightest = r.max(g).max(b);
+    if brightest < 1e-32 {
+        return [0; 4];
+    }
+    let exponent = (brightest.log2().floor() + 1.).min(127.) as i32;
+    let scale = 256. / 2f64.powi(exponent);
+    let mantissa = |c: f64| (c * scale).min(255.) as u8;
+    [mantissa(r), mantissa(g), mantissa(b), (exponent + 128) as u8]
+}
+
+// runs of equal bytes as (128 + length, byte), everything else as (length, bytes...)
+fn encode_channel(out: &mut Vec<u8>, bytes: &[u8]) {
+    let mut current = 0;
+    while current < bytes.len() {
+        // find the next run long enough to be worth encoding
+        let mut run_start = current;
+        let mut run_length = 0;
+        let mut previous_length = 0;
+        while run_length < MIN_RUN && run_start < bytes.len() {
+            run_start += run_length;
+            previous_length = run_length;
+            run_length = 1;
+            while run_start + run_length < bytes.len()
+                && run_length < 127
+                && bytes[run_start + run_length] == bytes[run_start]
+            {
+                run_length += 1;
+            }
+        }
+        // a short run right before the long one is still a run
+        if previous_length > 1 && previous_length == run_start - current {
+            out.extend_from_slice(&[128 + previous_length as u8, bytes[current]]);
+            current = run_start;
+        }
+        while current < run_start {
+            let count = (run_start - current).min(128);
+            out.push(count as u8);
+            out.extend_from_slice(&bytes[current..current + count]);
+            current += count;
+        }
+        if run_length >= MIN_RUN {
+            out.extend_from_slice(&[128 + run_length as u8, bytes[run_start]]);
+            current += run_length;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rgbe_keeps_shared_exponent() {
+        assert_eq!(to_rgbe([1., 0.5, 0.]), [128, 64, 0, 129]);
+        assert_eq!(to_rgbe([0., 0., 0.]), [0; 4]);
+    }
+
+    #[test]
+    fn rgbe_clamps_values_it_cannot_represent() {
+        assert_eq!(to_rgbe([f64::INFINITY, 0., 0.]), [255, 0, 0, 255]);
+        assert_eq!(to_rgbe([1e300, 1e300, 1e300]), [255, 255, 255, 255]);
+        assert_eq!(to_rgbe([f64::NAN, f64::NAN, f64::NAN]), [0; 4]);
+    }
+}
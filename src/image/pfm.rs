@@ -0,0 +1,15 @@
+use crate::color::Color;
+
+// Portable float map, 32-bit floats per channel.
+// The negative scale marks little endian data, rows are stored bottom to top.
+pub fn encode(width: usize, height: usize, pixels: &[Color]) -> Vec<u8> {
+    let mut out = format!("PF\n{width} {height}\n-1.0\n").into_bytes();
+    for row in pixels.chunks(width).take(height).rev() {
+        for pixel in row {
+            for channel in pixel.components() {
+                out.extend_from_slice(&(channel as f32).to_le_bytes());
+            }
+        }
+    }
+    out
+}
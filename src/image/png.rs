@@ -0,0 +1,239 @@
+use super::zlib;
+use std::io;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+// 8-bit RGB image, rows stored top to bottom
+pub fn encode(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut header = vec![];
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, color type 2 (truecolor), default compression, filter and no interlace
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib::compress(&filter(width, height, rgb)));
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+// Every row gets the filter with the smallest sum of absolute differences
+fn filter(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    const BPP: usize = 3;
+    let stride = width * BPP;
+    let zero_row = vec![0; stride];
+    let mut out = Vec::with_capacity((stride + 1) * height);
+    let mut candidate = vec![0; stride];
+    let mut best = vec![0; stride];
+    for y in 0..height {
+        let row = &rgb[y * stride..(y + 1) * stride];
+        let above = if y == 0 { &zero_row[..] } else { &rgb[(y - 1) * stride..y * stride] };
+        let mut best_filter = 0;
+        let mut best_score = u64::MAX;
+        for filter_type in 0..5u8 {
+            for i in 0..stride {
+                let a = if i >= BPP { row[i - BPP] } else { 0 };
+                let b = above[i];
+                let c = if i >= BPP { above[i - BPP] } else { 0 };
+                let predicted = match filter_type {
+                    0 => 0,
+                    1 => a,
+                    2 => b,
+                    3 => ((a as u16 + b as u16) / 2) as u8,
+                    _ => paeth(a, b, c),
+                };
+                candidate[i] = row[i].wrapping_sub(predicted);
+            }
+            let score = candidate.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+            if score < best_score {
+                best_score = score;
+                best_filter = filter_type;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        out.push(best_filter);
+        out.extend_from_slice(&best);
+    }
+    out
+}
+
+// Reads non-interlaced images of every color type and bit depth,
+// returns width, height and 8-bit RGB rows top to bottom. Alpha is dropped.
+pub fn decode(bytes: &[u8]) -> io::Result<(usize, usize, Vec<u8>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if bytes.len() < 8 || bytes[..8] != SIGNATURE {
+        return Err(invalid("not a PNG file"));
+    }
+    let mut header = None;
+    let mut palette = vec![];
+    let mut compressed = vec![];
+    let mut position = 8;
+    loop {
+        let length_bytes = bytes
+            .get(position..position + 4)
+            .ok_or_else(|| invalid("PNG file ends early"))?;
+        let length = u32::from_be_bytes(length_bytes.try_into().unwrap()) as usize;
+        let chunk = bytes
+            .get(position + 4..position + 12 + length)
+            .ok_or_else(|| invalid("PNG file ends early"))?;
+        let (kind_and_data, crc) = chunk.split_at(4 + length);
+        if crc32(kind_and_data) != u32::from_be_bytes(crc.try_into().unwrap()) {
+            return Err(invalid("PNG chunk checksum mismatch"));
+        }
+        let (kind, data) = kind_and_data.split_at(4);
+        match kind {
+            b"IHDR" if data.len() == 13 => header = Some(data.to_vec()),
+            b"PLTE" => palette = data.to_vec(),
+            b"IDAT" => compressed.extend_from_slice(data),
+            b"IEND" => break,
+            _ => {}
+        }
+        position += 12 + length;
+    }
+
+    let header = header.ok_or_else(|| invalid("PNG file has no header"))?;
+    let width = u32::from_be_bytes(header[0..4].try_into().unwrap()) as usize;
+    let height = u32::from_be_bytes(header[4..8].try_into().unwrap()) as usize;
+    if width == 0 || height == 0 {
+        return Err(invalid("PNG image is empty"));
+    }
+    let (bit_depth, color_type) = (header[8] as usize, header[9]);
+    if header[12] != 0 {
+        return Err(invalid("interlaced PNG files are not supported"));
+    }
+    let channels = match color_type {
+        0 | 3 => 1,
+        2 => 3,
+        4 => 2,
+        6 => 4,
+        _ => return Err(invalid("unknown PNG color type")),
+    };
+    if ![1, 2, 4, 8, 16].contains(&bit_depth) {
+        return Err(invalid("unknown PNG bit depth"));
+    }
+
+    let data = zlib::decompress(&compressed).map_err(invalid)?;
+    let stride = (width * channels * bit_depth).div_ceil(8);
+    let raw = unfilter(&data, stride, height, (channels * bit_depth).div_ceil(8))
+        .ok_or_else(|| invalid("PNG image data is corrupt"))?;
+
+    let max = ((1u32 << bit_depth.min(8)) - 1) as usize;
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for row in raw.chunks(stride) {
+        for x in 0..width {
+            // samples narrower than a byte are packed with the leftmost pixel in the high bits
+            let sample = |channel: usize| {
+                let index = x * channels + channel;
+                match bit_depth {
+                    16 => row[index * 2] as usize,
+                    8 => row[index] as usize,
+                    depth => {
+                        let bit = index * depth;
+                        (row[bit / 8] as usize >> (8 - depth - bit % 8)) & max
+                    }
+                }
+            };
+            let pixel = match color_type {
+                3 => {
+                    let entry = palette
+                        .get(sample(0) * 3..sample(0) * 3 + 3)
+                        .ok_or_else(|| invalid("PNG palette index out of range"))?;
+                    [entry[0], entry[1], entry[2]]
+                }
+                0 | 4 => [(sample(0) * 255 / max) as u8; 3],
+                _ => [sample(0) as u8, sample(1) as u8, sample(2) as u8],
+            };
+            rgb.extend_from_slice(&pixel);
+        }
+    }
+    Ok((width, height, rgb))
+}
+
+// undoes the per-row filters, `bpp` is the distance to the byte of the previous pixel
+fn unfilter(data: &[u8], stride: usize, height: usize, bpp: usize) -> Option<Vec<u8>> {
+    let mut out = vec![0u8; stride * height];
+    for y in 0..height {
+        let line = data.get(y * (stride + 1)..(y + 1) * (stride + 1))?;
+        let (filter_type, filtered) = (line[0], &line[1..]);
+        let (done, rest) = out.split_at_mut(y * stride);
+        let above = if y == 0 { None } else { Some(&done[(y - 1) * stride..]) };
+        let row = &mut rest[..stride];
+        for i in 0..stride {
+            let a = if i >= bpp { row[i - bpp] } else { 0 };
+            let b = above.map_or(0, |above| above[i]);
+            let c = if i >= bpp { above.map_or(0, |above| above[i - bpp]) } else { 0 };
+            let predicted = match filter_type {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => ((a as u16 + b as u16) / 2) as u8,
+                4 => paeth(a, b, c),
+                _ => return None,
+            };
+            row[i] = filtered[i].wrapping_add(predicted);
+        }
+    }
+    Some(out)
+}
+
+fn paeth(a: u8, b: u8, c: u8) -> u8 {
+    let p = a as i16 + b as i16 - c as i16;
+    let pa = (p - a as i16).abs();
+    let pb = (p - b as i16).abs();
+    let pc = (p - c as i16).abs();
+    if pa <= pb && pa <= pc {
+        a
+    } else if pb <= pc {
+        b
+    } else {
+        c
+    }
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (n, entry) in table.iter_mut().enumerate() {
+        let mut c = n as u32;
+        for _ in 0..8 {
+            c = if c & 1 == 1 { 0xedb88320 ^ (c >> 1) } else { c >> 1 };
+        }
+        *entry = c;
+    }
+    let mut crc = 0xffffffff;
+    for &byte in data {
+        crc = table[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8);
+    }
+    crc ^ 0xffffffff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let rgb = [255, 0, 0, 0, 128, 255];
+        assert_eq!(decode(&encode(2, 1, &rgb)).unwrap(), (2, 1, rgb.to_vec()));
+    }
+
+    #[test]
+    fn zero_width_is_invalid_data() {
+        let mut bytes = encode(1, 1, &[0, 0, 0]);
+        // IHDR starts after the signature, its width follows the length and the kind
+        bytes[16..20].copy_from_slice(&[0; 4]);
+        let crc = crc32(&bytes[12..29]);
+        bytes[29..33].copy_from_slice(&crc.to_be_bytes());
+        let error = decode(&bytes).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{self, Write};
+
+// plain text (P3) 8-bit RGB image, rows stored top to bottom
+pub fn encode(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    let mut out = vec![];
+    // writing into a Vec cannot fail
+    writeln!(out, "P3").unwrap();
+    writeln!(out, "{width} {height}").unwrap();
+    writeln!(out, "255").unwrap();
+    for pixel in rgb.chunks(3) {
+        writeln!(out, "{} {} {}", pixel[0], pixel[1], pixel[2]).unwrap();
+    }
+    out
+}
+
+// Reads plain (P3) and binary (P6) files with any maximum value,
+// returns width, height and 8-bit RGB rows top to bottom
+pub fn decode(bytes: &[u8]) -> io::Result<(usize, usize, Vec<u8>)> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    let mut position = 0;
+    let magic = next_token(bytes, &mut position).ok_or_else(|| invalid("empty file"))?;
+    let binary = match magic {
+        b"P3" => false,
+        b"P6" => true,
+        _ => return Err(invalid("not a P3 or P6 file")),
+    };
+    let mut header = [0usize; 3];
+    for value in header.iter_mut() {
+        *value = next_token(bytes, &mut position)
+            .and_then(parse_number)
+            .ok_or_else(|| invalid("invalid header"))?;
+    }
+    let [width, height, max_value] = header;
+    if max_value == 0 || max_value > 65535 {
+        return Err(invalid("invalid maximum value"));
+    }
+    let count = width * height * 3;
+    let scale = |value: usize| (value.min(max_value) * 255 + max_value / 2) / max_value;
+
+    let mut rgb = Vec::with_capacity(count);
+    if binary {
+        // a single whitespace byte separates the header from the data
+        let data = &bytes[(position + 1).min(bytes.len())..];
+        let sample_size = if max_value < 256 { 1 } else { 2 };
+        if data.len() < count * sample_size {
+            return Err(invalid("file is too short"));
+        }
+        for sample in data.chunks(sample_size).take(count) {
+            let value = sample.iter().fold(0, |v, &byte| v << 8 | byte as usize);
+            rgb.push(scale(value) as u8);
+        }
+    } else {
+        for _ in 0..count {
+            let value = next_token(bytes, &mut position)
+                .and_then(parse_number)
+                .ok_or_else(|| invalid("file is too short"))?;
+            rgb.push(scale(value) as u8);
+        }
+    }
+    Ok((width, height, rgb))
+}
+
+// next whitespace separated token, skipping `#` comments
+fn next_token<'a>(bytes: &'a [u8], position: &mut usize) -> Option<&'a [u8]> {
+    loop {
+        while *position < bytes.len() && bytes[*position].is_ascii_whitespace() {
+            *position += 1;
+        }
+        if bytes.get(*position) != Some(&b'#') {
+            break;
+        }
+        while *position < bytes.len() && bytes[*position] != b'\n' {
+            *position += 1;
+        }
+    }
+    let start = *position;
+    while *position < bytes.len() && !bytes[*position].is_ascii_whitespace() {
+        *position += 1;
+    }
+    (start < *position).then(|| &bytes[start..*position])
+}
+
+fn parse_number(token: &[u8]) -> Option<usize> {
+    std::str::from_utf8(token).ok()?.parse().ok()
+}
@@ -0,0 +1,64 @@
+mod ambient_occlusion;
+mod debug;
+mod path;
+mod whitted;
+
+pub use ambient_occlusion::AmbientOcclusion;
+pub use debug::{BvhCost, Depth, MaterialIds, Normals, Uv};
+pub use path::PathTracer;
+pub use whitted::Whitted;
+
+use crate::{color::Color, ray::Ray, raytracer::RayTracer, sampler::Sampler};
+
+// how far ambient occlusion looks for nearby surfaces, in scene units
+pub const DEFAULT_AO_DISTANCE: f64 = 1.;
+
+// Computes the color arriving at the camera along a ray
+pub trait Integrator: Send + Sync {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, sampler: &mut dyn Sampler) -> Color;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IntegratorKind {
+    // diffuse and specular highlights from the lights plus one bounce per surface, fast but not physically based
+    Whitted,
+    // physically based path tracing, slower but light bounces between surfaces like in reality
+    Path,
+    AmbientOcclusion { distance: f64 },
+    Normals,
+    Depth,
+    Uv,
+    MaterialIds,
+    BvhCost,
+}
+
+impl IntegratorKind {
+    // `ao_distance` is only used by ambient occlusion
+    pub fn from_name(name: &str, ao_distance: f64) -> Option<Self> {
+        match name {
+            "whitted" => Some(IntegratorKind::Whitted),
+            "path" => Some(IntegratorKind::Path),
+            "ambient_occlusion" => Some(IntegratorKind::AmbientOcclusion {
+                distance: ao_distance,
+            }),
+            "normals" => Some(IntegratorKind::Normals),
+            "depth" => Some(IntegratorKind::Depth),
+            "uv" => Some(IntegratorKind::Uv),
+            "material_ids" => Some(IntegratorKind::MaterialIds),
+            "bvh_cost" => Some(IntegratorKind::BvhCost),
+            _ => None,
+        }
+    }
+    pub fn build(self) -> Box<dyn Integrator> {
+        match self {
+            IntegratorKind::Whitted => Box::new(Whitted),
+            IntegratorKind::Path => Box::new(PathTracer),
+            IntegratorKind::AmbientOcclusion { distance } => Box::new(AmbientOcclusion { distance }),
+            IntegratorKind::Normals => Box::new(Normals),
+            IntegratorKind::Depth => Box::new(Depth),
+            IntegratorKind::Uv => Box::new(Uv),
+            IntegratorKind::MaterialIds => Box::new(MaterialIds),
+            IntegratorKind::BvhCost => Box::new(BvhCost),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::{
+    color::Color,
+    point3d::Point3D,
+    ray::Ray,
+    raytracer::RayTracer,
+    sampler::Sampler,
+};
+
+use super::Integrator;
+
+// Gray shading by how open the surface is: white where nothing is within `distance`,
+// darker in corners and crevices. Ignores lights and materials.
+pub struct AmbientOcclusion {
+    pub distance: f64,
+}
+
+impl Integrator for AmbientOcclusion {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, sampler: &mut dyn Sampler) -> Color {
+        let mut intersection = tracer.hit_scene(&ray);
+        if !intersection.hit_anything {
+            return Color::white();
+        }
+        intersection.material.unwrap().apply_bump(&mut intersection);
+        // cosine weighted, like light falling onto a matte surface
+        let direction = intersection.normal + Point3D::on_unit_sphere(sampler.next_2d());
+        if direction.near_zero() {
+            return Color::white();
+        }
+        let direction = direction.unit_vector();
+        if tracer.occluded(&intersection.point, &direction, self.distance) {
+            Color::black()
+        } else {
+            Color::white()
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use crate::{
+    color::Color,
+    objects::Intersection,
+    ray::Ray,
+    raytracer::RayTracer,
+    sampler::Sampler,
+};
+
+use super::Integrator;
+
+// Views of the scene data rather than its lighting, for finding problems with
+// geometry, textures or performance. Rays that miss everything are black.
+
+// shading normal after bump mapping, x y z as red green blue
+pub struct Normals;
+
+impl Integrator for Normals {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, _sampler: &mut dyn Sampler) -> Color {
+        let mut intersection = tracer.hit_scene(&ray);
+        if !intersection.hit_anything {
+            return Color::black();
+        }
+        intersection.material.unwrap().apply_bump(&mut intersection);
+        let n = intersection.normal;
+        Color::new(n.x() + 1., n.y() + 1., n.z() + 1.) * 0.5
+    }
+}
+
+// distance to the camera, white up close fading to black, half bright at `DEPTH_SCALE`
+pub struct Depth;
+
+const DEPTH_SCALE: f64 = 10.;
+
+impl Integrator for Depth {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, _sampler: &mut dyn Sampler) -> Color {
+        let intersection = tracer.hit_scene(&ray);
+        if !intersection.hit_anything {
+            return Color::black();
+        }
+        let distance = intersection.t * ray.direction.length();
+        Color::white() * (DEPTH_SCALE / (DEPTH_SCALE + distance))
+    }
+}
+
+// texture coordinates, u as red and v as green, repeating outside of 0 - 1
+pub struct Uv;
+
+impl Integrator for Uv {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, _sampler: &mut dyn Sampler) -> Color {
+        let intersection = tracer.hit_scene(&ray);
+        if !intersection.hit_anything {
+            return Color::black();
+        }
+        let (u, v) = intersection.uv;
+        Color::new(u.rem_euclid(1.), v.rem_euclid(1.), 0.)
+    }
+}
+
+// a color for every material declared in the scene file
+pub struct MaterialIds;
+
+impl Integrator for MaterialIds {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, _sampler: &mut dyn Sampler) -> Color {
+        let intersection = tracer.hit_scene(&ray);
+        let Some(material) = intersection.material else {
+            return Color::black();
+        };
+        // fully saturated hues, golden ratio steps keep neighbouring ids far apart
+        let hue = (material.id() as f64 * 0.618034).fract() * 6.;
+        let channel = |center: f64| (2. - (hue - center).abs()).clamp(0., 1.);
+        Color::new(
+            channel(0.).max(channel(6.)),
+            channel(2.),
+            channel(4.),
+        )
+    }
+}
+
+// bounding boxes and objects tested by the camera ray, blue for few up to red for `MAX_COST`
+pub struct BvhCost;
+
+const MAX_COST: f64 = 64.;
+
+impl Integrator for BvhCost {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, _sampler: &mut dyn Sampler) -> Color {
+        let mut hit_record = Intersection::new();
+        let (_, cost) = tracer.scene.hit_with_cost(&ray, &mut hit_record);
+        let x = (cost as f64 / MAX_COST).min(1.);
+        Color::new(x, 1. - (2. * x - 1.).abs(), 1. - x)
+    }
+}
@@ -0,0 +1,199 @@
+use crate::{
+    color::Color,
+    light::Illuminates,
+    material::{Material, Scatterable},
+    objects::Intersection,
+    point3d::Point3D,
+    ray::Ray,
+    raytracer::{RayTracer, BACKGROUND},
+    sampler::Sampler,
+};
+use std::f64::consts::PI;
+
+use super::Integrator;
+
+// bounces that always happen before Russian roulette may end a path
+const MIN_BOUNCES: usize = 3;
+
+// Unidirectional path tracer. Every diffuse hit samples the lights directly (next
+// event estimation) and then follows the one direction the material picks. Sphere
+// lights can be found both ways, multiple importance sampling weighs the two
+// estimates so neither is counted twice. Paths end by Russian roulette, which keeps
+// the image unbiased without a fixed bounce limit.
+pub struct PathTracer;
+
+impl Integrator for PathTracer {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, sampler: &mut dyn Sampler) -> Color {
+        let mut radiance = Color::black();
+        let mut throughput = Color::white();
+        let mut ray = ray;
+        // where the last bounce happened and the pdf of its direction,
+        // None for camera rays and after mirrors or glass, which lights can't be sampled for
+        let mut diffuse_bounce: Option<(Point3D, f64)> = None;
+        for bounce in 0.. {
+            let mut intersection = tracer.hit_scene(&ray);
+            let max_distance = if intersection.hit_anything {
+                intersection.t
+            } else {
+                f64::INFINITY
+            };
+            if let Some(emitted) = light_hit(tracer, &ray, max_distance, diffuse_bounce) {
+                radiance = radiance + throughput * emitted;
+                break;
+            }
+            if !intersection.hit_anything {
+                radiance = radiance + throughput * BACKGROUND;
+                break;
+            }
+
+            let material = intersection.material.unwrap();
+            material.apply_bump(&mut intersection);
+            // glowing objects can only be found by bouncing into them
+            if let Material::Light(_) = material {
+                radiance = radiance + throughput * Color::white() * material.intensity();
+                break;
+            }
+            let albedo = material.diffuse_color(&intersection);
+            if let Some(albedo) = albedo {
+                let direct = direct_light(tracer, &intersection, albedo, sampler);
+                radiance = radiance + throughput * direct;
+            }
+
+            let Some((next, attenuation)) = material.scatter(&ray, &intersection, sampler) else {
+                break;
+            };
+            // scatter picks diffuse directions by cos / pi, so the attenuation is the albedo
+            throughput = throughput * attenuation;
+            diffuse_bounce = albedo.map(|_| {
+                let cos = next.direction.unit_vector().dot(&intersection.normal).max(0.);
+                (intersection.point, cos / PI)
+            });
+
+            if bounce >= MIN_BOUNCES {
+                // dim paths carry little light, most of them end here and the
+                // survivors are brightened to make up for the ones that ended
+                let [r, g, b] = throughput.components();
+                let survival = r.max(g).max(b).clamp(0.05, 0.95);
+                if sampler.next_1d() >= survival {
+                    break;
+                }
+                throughput = throughput * (1. / survival);
+            }
+            ray = next;
+        }
+        radiance
+    }
+}
+
+// Lambertian reflection of every light that isn't blocked
+fn direct_light(
+    tracer: &RayTracer,
+    intersection: &Intersection,
+    albedo: Color,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    let brdf = albedo * (1. / PI);
+    let mut total = Color::black();
+    for light in &tracer.lights {
+        let Some(sample) = light.illuminate(&intersection.point, sampler) else {
+            continue;
+        };
+        let cos = sample.direction.dot(&intersection.normal);
+        if cos <= 0. || tracer.cast_ray_to_light(&intersection.point, &sample) {
+            continue;
+        }
+        // points, spots and the sun can't be hit by bounces, their samples count fully
+        let weight = match sample.pdf {
+            Some(light_pdf) => power_heuristic(light_pdf, cos / PI),
+            None => 1.,
+        };
+        total = total + brdf * sample.radiance * (cos * weight);
+    }
+    total
+}
+
+// light from the nearest sphere light the ray hits before `max_distance`
+fn light_hit(
+    tracer: &RayTracer,
+    ray: &Ray,
+    max_distance: f64,
+    diffuse_bounce: Option<(Point3D, f64)>,
+) -> Option<Color> {
+    let (light, _, emitted) = tracer
+        .lights
+        .iter()
+        .filter_map(|light| light.hit(ray).map(|(t, emitted)| (light, t, emitted)))
+        .filter(|(_, t, _)| *t < max_distance)
+        .min_by(|a, b| a.1.total_cmp(&b.1))?;
+    let weight = match diffuse_bounce {
+        // from inside the light, light sampling falls back to a point light with full
+        // weight, so the light must not be counted again here
+        Some((point, _)) if light.pdf(&point) == 0. => 0.,
+        Some((point, bsdf_pdf)) => power_heuristic(bsdf_pdf, light.pdf(&point)),
+        None => 1.,
+    };
+    Some(emitted * weight)
+}
+
+// weight of an estimate with density `pdf` when `other_pdf` could have found the same light
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let (a, b) = (pdf * pdf, other_pdf * other_pdf);
+    if a + b == 0. {
+        0.
+    } else {
+        a / (a + b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        config::Config,
+        sampler::{SampleSource, SamplerKind},
+    };
+    use std::path::Path;
+
+    // Average radiance arriving at a point on the inside of a closed matte sphere of
+    // radius 1 with a small light in its center. Every point of a sphere sees every
+    // other point equally, so the light arriving after one bounce is the same all over
+    // the wall and every further bounce multiplies it by the albedo.
+    fn inside_lit_sphere(albedo: f64, intensity: f64) -> f64 {
+        let source = format!(
+            "camera origin=(0.5, 0, 0) target=(0.5, 0, -1) up=(0, 1, 0) fov=10\n\
+             sphere_light center=(0, 0, 0) radius=0.03 intensity={intensity}\n\
+             sphere center=(0, 0, 0) radius=1 material=matte color=({albedo}, {albedo}, {albedo})"
+        );
+        let config = Config::parse(&source, Path::new("")).unwrap_or_else(|e| panic!("{e}"));
+        let tracer = RayTracer::new(config);
+        const PATHS: usize = 20000;
+        let mut sampler = SampleSource::new(SamplerKind::Sobol, PATHS, 1);
+        let mut total = 0.;
+        for i in 0..PATHS {
+            sampler.start_pixel_sample(0, 0, i);
+            let ray = Ray::new(Point3D::new(0.5, 0., 0.), Point3D::new(0., 0., -1.));
+            total += PathTracer.radiance(&tracer, ray, &mut sampler).luminance();
+        }
+        total / PATHS as f64
+    }
+
+    #[test]
+    fn interreflection_matches_the_closed_form() {
+        for (albedo, intensity) in [(0.5, 1.), (0.8, 2.)] {
+            // a sphere light gives the irradiance of a point light, intensity / distance^2
+            let first_bounce = albedo * intensity / PI;
+            let expected = first_bounce / (1. - albedo);
+            let actual = inside_lit_sphere(albedo, intensity);
+            let error = (actual - expected).abs() / expected;
+            assert!(error < 0.02, "albedo {albedo}: {actual} instead of {expected}");
+        }
+    }
+
+    #[test]
+    fn power_heuristic_weights_add_up_to_one() {
+        for (a, b) in [(1., 1.), (0.2, 5.), (3., 0.01)] {
+            assert!((power_heuristic(a, b) + power_heuristic(b, a) - 1.).abs() < 1e-12);
+        }
+        assert_eq!(power_heuristic(0., 0.), 0.);
+    }
+}
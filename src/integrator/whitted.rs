@@ -0,0 +1,106 @@
+use crate::{
+    color::Color,
+    light::{Illuminates, LightSample, LightSource},
+    material::{Material, Scatterable},
+    objects::Intersection,
+    ray::Ray,
+    raytracer::{RayTracer, BACKGROUND},
+    sampler::Sampler,
+};
+
+use super::Integrator;
+
+const MAX_DEPTH: i32 = 50;
+
+// The original shading: half of the light bouncing off every surface plus
+// diffuse and specular highlights from the lights. Fast, but not energy conserving.
+pub struct Whitted;
+
+impl Integrator for Whitted {
+    fn radiance(&self, tracer: &RayTracer, ray: Ray, sampler: &mut dyn Sampler) -> Color {
+        find_pixel_color(tracer, &ray, MAX_DEPTH, sampler)
+    }
+}
+
+fn find_pixel_color(tracer: &RayTracer, ray: &Ray, depth: i32, sampler: &mut dyn Sampler) -> Color {
+    if depth <= 0 {
+        return Color::black();
+    }
+    let mut intersection = tracer.hit_scene(ray);
+
+    if intersection.hit_anything {
+        intersection.material.unwrap().apply_bump(&mut intersection);
+
+        let bounce = intersection.material.unwrap().scatter(ray, &intersection, sampler);
+
+        if bounce.is_none() {
+            // glowing surfaces emit light, everything else absorbed the ray
+            if let Material::Light(_) = intersection.material.unwrap() {
+                return Color::white() * intersection.material.unwrap().intensity();
+            }
+            return Color::black();
+        }
+
+        let (bounce_ray, color) = bounce.unwrap();
+        let target_color = find_pixel_color(tracer, &bounce_ray, depth - 1, sampler);
+        // glass only passes light on, it has no diffuse or specular term of its own
+        if let Material::Dielectric(_) = intersection.material.unwrap() {
+            return color * target_color;
+        }
+        let pixel_color = color * target_color * 0.5;
+        // every light that isn't blocked adds its own direct contribution
+        tracer.lights.iter().fold(pixel_color, |pixel, light| {
+            pixel + direct_light(tracer, light, &intersection, sampler)
+        })
+    } else {
+        BACKGROUND
+    }
+}
+
+// average over the light's samples, partly blocked area lights give soft shadows
+fn direct_light(
+    tracer: &RayTracer,
+    light: &LightSource,
+    intersection: &Intersection,
+    sampler: &mut dyn Sampler,
+) -> Color {
+    let samples = light.samples();
+    let mut total = Color::black();
+    for _ in 0..samples {
+        match light.illuminate(&intersection.point, sampler) {
+            Some(sample) if !tracer.cast_ray_to_light(&intersection.point, &sample) => {
+                total = total + light_specular_diffuse_adjustment(tracer, intersection, &sample);
+            }
+            _ => {}
+        }
+    }
+    total * (1. / samples as f64)
+}
+
+fn light_specular_diffuse_adjustment(
+    tracer: &RayTracer,
+    intersection: &Intersection,
+    light: &LightSample,
+) -> Color {
+    const DIFFUSE_COEF: f64 = 0.4;
+    const SPECULAR_COEF: f64 = 0.2;
+
+    let light_vec = light.direction;
+    let light_angle = light_vec.dot(&intersection.normal);
+    let reflection_vec =
+        light_vec * -2. * light_vec.dot(&intersection.normal) * intersection.normal;
+    let view_direction = (intersection.point - tracer.camera.origin).unit_vector();
+    let reflection_angle = reflection_vec.dot(&view_direction);
+
+    let diffuse_reflection = if light_angle < 0. { 0. } else { light_angle };
+    let specular = if reflection_angle < 0. {
+        0.
+    } else {
+        reflection_angle
+    };
+    let specular_reflection = specular.powf(1.);
+
+    (Color::white() * DIFFUSE_COEF * diffuse_reflection
+        + Color::white() * SPECULAR_COEF * specular_reflection)
+        * light.radiance
+}
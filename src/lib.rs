@@ -0,0 +1,27 @@
+pub mod aabb;
+pub mod bump;
+pub mod camera;
+pub mod color;
+pub mod image;
+pub mod integrator;
+pub mod light;
+pub mod material;
+pub mod matrix;
+pub mod noise;
+pub mod objects;
+pub mod point3d;
+pub mod random;
+pub mod ray;
+pub mod raytracer;
+pub mod sampler;
+pub mod config;
+pub mod filter;
+pub mod scene;
+pub mod texture;
+pub mod tonemap;
+
+use camera::Camera;
+use color::*;
+use image::*;
+use objects::{Bvh, Hittable};
+use point3d::*;
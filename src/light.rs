@@ -0,0 +1,276 @@
+use crate::{color::Color, point3d::Point3D, ray::Ray, sampler::Sampler};
+use std::f64::consts::PI;
+
+// Light arriving at a shaded point from one light source
+pub struct LightSample {
+    // unit vector from the shaded point towards the light
+    pub direction: Point3D,
+    // how far a shadow ray has to travel, infinite for directional lights
+    pub distance: f64,
+    pub radiance: Color,
+    // density of the sampled direction per solid angle, None for lights rays can't hit
+    pub pdf: Option<f64>,
+}
+
+pub trait Illuminates {
+    // None when the point gets no light at all, e.g. outside of a spot cone.
+    // Area lights pick a different point of their surface with every sampler value.
+    fn illuminate(&self, point: &Point3D, sampler: &mut dyn Sampler) -> Option<LightSample>;
+}
+
+pub enum LightSource {
+    Point(PointLight),
+    Directional(DirectionalLight),
+    Spot(SpotLight),
+    Sphere(SphereLight),
+}
+
+impl LightSource {
+    // shadow rays to average per shaded point, only area lights need more than one
+    pub fn samples(&self) -> usize {
+        match self {
+            LightSource::Sphere(l) => l.samples,
+            _ => 1,
+        }
+    }
+    // distance along the ray to the light's surface and the radiance it emits there,
+    // only sphere lights have a surface
+    pub fn hit(&self, ray: &Ray) -> Option<(f64, Color)> {
+        match self {
+            LightSource::Sphere(l) => l.hit(ray),
+            _ => None,
+        }
+    }
+    // density with which `illuminate` picks a direction from `point` that reaches the light
+    pub fn pdf(&self, point: &Point3D) -> f64 {
+        match self {
+            LightSource::Sphere(l) => l.pdf(point),
+            _ => 0.,
+        }
+    }
+}
+
+impl Illuminates for LightSource {
+    fn illuminate(&self, point: &Point3D, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        match self {
+            LightSource::Point(l) => l.illuminate(point, sampler),
+            LightSource::Directional(l) => l.illuminate(point, sampler),
+            LightSource::Spot(l) => l.illuminate(point, sampler),
+            LightSource::Sphere(l) => l.illuminate(point, sampler),
+        }
+    }
+}
+
+// inverse square falloff from a position, capped at `min_distance`
+fn positional_sample(
+    position: &Point3D,
+    min_distance: f64,
+    color: Color,
+    intensity: f64,
+    point: &Point3D,
+) -> LightSample {
+    let to_light = *position - *point;
+    let distance_squared = to_light.length_squared();
+    let distance = distance_squared.sqrt();
+    let falloff = distance_squared.max(min_distance * min_distance);
+    LightSample {
+        direction: to_light / distance,
+        distance,
+        radiance: color * (intensity / falloff),
+        pdf: None,
+    }
+}
+
+// Shines equally in every direction from a single point
+pub struct PointLight {
+    position: Point3D,
+    color: Color,
+    intensity: f64,
+}
+
+impl PointLight {
+    pub fn new(position: Point3D, color: Color, intensity: f64) -> Self {
+        Self {
+            position,
+            color,
+            intensity,
+        }
+    }
+}
+
+impl Illuminates for PointLight {
+    fn illuminate(&self, point: &Point3D, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        Some(positional_sample(&self.position, 0., self.color, self.intensity, point))
+    }
+}
+
+// Very distant light like the sun, all rays are parallel and never fade
+pub struct DirectionalLight {
+    // direction the light travels in
+    direction: Point3D,
+    color: Color,
+    intensity: f64,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Point3D, color: Color, intensity: f64) -> Self {
+        Self {
+            direction: direction.unit_vector(),
+            color,
+            intensity,
+        }
+    }
+}
+
+impl Illuminates for DirectionalLight {
+    fn illuminate(&self, _point: &Point3D, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        Some(LightSample {
+            direction: self.direction * -1.,
+            distance: f64::INFINITY,
+            radiance: self.color * self.intensity,
+            pdf: None,
+        })
+    }
+}
+
+// Point light limited to a cone, fading out between the inner and outer angle
+pub struct SpotLight {
+    position: Point3D,
+    direction: Point3D,
+    cos_inner: f64,
+    cos_outer: f64,
+    color: Color,
+    intensity: f64,
+}
+
+impl SpotLight {
+    // angles in radians, measured from the cone axis
+    pub fn new(
+        position: Point3D,
+        direction: Point3D,
+        inner_angle: f64,
+        outer_angle: f64,
+        color: Color,
+        intensity: f64,
+    ) -> Self {
+        let inner_angle = inner_angle.min(outer_angle);
+        Self {
+            position,
+            direction: direction.unit_vector(),
+            cos_inner: inner_angle.cos(),
+            cos_outer: outer_angle.cos(),
+            color,
+            intensity,
+        }
+    }
+}
+
+impl Illuminates for SpotLight {
+    fn illuminate(&self, point: &Point3D, _sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let mut sample = positional_sample(&self.position, 0., self.color, self.intensity, point);
+        let cos_angle = (sample.direction * -1.).dot(&self.direction);
+        if cos_angle <= self.cos_outer {
+            return None;
+        }
+        if cos_angle < self.cos_inner {
+            // smoothstep between the edges of the cone
+            let x = (cos_angle - self.cos_outer) / (self.cos_inner - self.cos_outer);
+            sample.radiance = sample.radiance * (x * x * (3. - 2. * x));
+        }
+        Some(sample)
+    }
+}
+
+// Glowing sphere. Shadow rays aim at random points of its surface,
+// which softens the shadow edges. Without a radius it lights like a point light.
+pub struct SphereLight {
+    pub center: Point3D,
+    pub radius: f64,
+    pub samples: usize,
+    color: Color,
+    intensity: f64,
+}
+
+impl SphereLight {
+    pub fn new(center: Point3D, radius: f64, samples: usize, color: Color, intensity: f64) -> Self {
+        Self {
+            center,
+            radius: radius.max(0.),
+            samples: samples.max(1),
+            color,
+            intensity,
+        }
+    }
+    // radiance of the surface, chosen so that a distant sphere light
+    // is as bright as a point light with the same intensity
+    fn radiance(&self) -> Color {
+        self.color * (self.intensity / (PI * self.radius * self.radius))
+    }
+    // cosine of the half angle of the cone the sphere fills when seen from a point outside
+    fn cos_theta_max(&self, distance_squared: f64) -> f64 {
+        (1. - self.radius * self.radius / distance_squared).sqrt()
+    }
+    fn hit(&self, ray: &Ray) -> Option<(f64, Color)> {
+        const T_MIN: f64 = 0.001;
+        if self.radius <= 0. {
+            return None;
+        }
+        let oc = ray.origin - self.center;
+        let a = ray.direction.length_squared();
+        let half_b = oc.dot(&ray.direction);
+        let c = oc.length_squared() - self.radius * self.radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0. {
+            return None;
+        }
+        let sqrtd = discriminant.sqrt();
+        let t = [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a]
+            .into_iter()
+            .find(|t| *t > T_MIN)?;
+        Some((t, self.radiance()))
+    }
+    fn pdf(&self, point: &Point3D) -> f64 {
+        let distance_squared = (self.center - *point).length_squared();
+        if self.radius <= 0. || distance_squared <= self.radius * self.radius {
+            return 0.;
+        }
+        1. / (2. * PI * (1. - self.cos_theta_max(distance_squared)))
+    }
+}
+
+impl Illuminates for SphereLight {
+    fn illuminate(&self, point: &Point3D, sampler: &mut dyn Sampler) -> Option<LightSample> {
+        let to_center = self.center - *point;
+        let distance_squared = to_center.length_squared();
+        let radius_squared = self.radius * self.radius;
+        if self.radius <= 0. || distance_squared <= radius_squared {
+            // no surface to aim at from inside the light or for a point
+            return Some(positional_sample(&self.center, self.radius, self.color, self.intensity, point));
+        }
+
+        // sample a direction uniformly inside the cone the sphere fills when seen from the point
+        let distance = distance_squared.sqrt();
+        let w = to_center / distance;
+        let (u, v) = w.orthonormal_basis();
+        let cos_theta_max = self.cos_theta_max(distance_squared);
+        let (r1, r2) = sampler.next_2d();
+        let cos_theta = 1. - r1 * (1. - cos_theta_max);
+        let sin_theta = (1. - cos_theta * cos_theta).max(0.).sqrt();
+        let phi = 2. * PI * r2;
+        let direction = u * (phi.cos() * sin_theta) + v * (phi.sin() * sin_theta) + w * cos_theta;
+
+        // nearest intersection with the sphere along the sampled direction
+        let projection = to_center.dot(&direction);
+        let half_chord = (radius_squared - (distance_squared - projection * projection)).max(0.).sqrt();
+        let surface_distance = projection - half_chord;
+
+        // uniform cone sampling has a pdf of 1 / solid angle
+        let solid_angle = 2. * PI * (1. - cos_theta_max);
+        Some(LightSample {
+            direction,
+            distance: surface_distance,
+            radiance: self.radiance() * solid_angle,
+            pdf: Some(1. / solid_angle),
+        })
+    }
+}
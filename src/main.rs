@@ -1,26 +1,96 @@
-mod camera;
-mod color;
-mod image;
-mod material;
-mod objects;
-mod point3d;
-mod ray;
-mod raytracer;
-mod config;
+use rt_test::{
+    config::Config,
+    image::OutputFormat,
+    integrator::{IntegratorKind, DEFAULT_AO_DISTANCE},
+    raytracer::RayTracer,
+};
+use std::{env, fs, path::Path, process};
 
-use camera::Camera;
-use color::*;
-use image::*;
-use material::*;
-use objects::{Hittable, Sphere};
-use point3d::*;
-use raytracer::*;
-use config::*;
 
+const USAGE: &str = "[--seed <n>] [--threads <n>] [--integrator <name>] <scene_file> <output.png|output.ppm|output.hdr|output.pfm>";
+
+// command line settings that override the scene file
+struct Options {
+    seed: Option<u64>,
+    threads: Option<usize>,
+    integrator: Option<IntegratorKind>,
+    files: Vec<String>,
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        seed: None,
+        threads: None,
+        integrator: None,
+        files: vec![],
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" | "--threads" => {
+                let value = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                let number = value
+                    .parse::<u64>()
+                    .map_err(|_| format!("`{arg}` must be a whole number, found `{value}`"))?;
+                if arg == "--seed" {
+                    options.seed = Some(number);
+                } else {
+                    options.threads = Some(number as usize);
+                }
+            }
+            "--integrator" => {
+                let name = args.next().ok_or_else(|| format!("`{arg}` needs a value"))?;
+                let integrator = IntegratorKind::from_name(name, DEFAULT_AO_DISTANCE)
+                    .ok_or_else(|| format!("unknown integrator `{name}`"))?;
+                options.integrator = Some(integrator);
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{flag}`")),
+            file => options.files.push(file.to_string()),
+        }
+    }
+    if options.files.len() != 2 {
+        return Err("expected a scene file and an output file".to_string());
+    }
+    Ok(options)
+}
 
 fn main() {
-    let c = Config::new();
+    let args: Vec<String> = env::args().collect();
+    let options = parse_options(&args[1..]).unwrap_or_else(|e| {
+        eprintln!("error: {e}");
+        eprintln!("Usage: {} {USAGE}", args[0]);
+        process::exit(2);
+    });
+    let path = &options.files[0];
+    let output = Path::new(&options.files[1]);
+    if OutputFormat::from_path(output).is_none() {
+        eprintln!("error: unsupported output format for {}, use .png, .ppm, .hdr or .pfm", output.display());
+        process::exit(2);
+    }
+    let source = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("error: cannot read {path}: {e}");
+        process::exit(1);
+    });
+    let base_dir = Path::new(path).parent().unwrap_or(Path::new(""));
+    let mut c = Config::parse(&source, base_dir).unwrap_or_else(|e| {
+        eprintln!("error: {path}:{e}");
+        process::exit(1);
+    });
+
+    if let Some(seed) = options.seed {
+        c.seed = seed;
+    }
+    if let Some(integrator) = options.integrator {
+        c.integrator = integrator;
+    }
 
-    let mut ray_tracer = RayTracer::new(c.image, c.camera, c.objects, c.light);
+    let mut ray_tracer = RayTracer::new(c);
+    if let Some(threads) = options.threads {
+        ray_tracer.set_threads(threads);
+    }
     ray_tracer.render();
+    if let Err(e) = ray_tracer.image.save(output) {
+        eprintln!("error: cannot write {}: {e}", output.display());
+        process::exit(1);
+    }
 }
@@ -1,12 +1,28 @@
-use crate::{color::Color, objects::Intersection, point3d::Point3D, ray::Ray};
+use crate::{
+    bump::Bump,
+    color::Color,
+    objects::Intersection,
+    point3d::Point3D,
+    ray::Ray,
+    sampler::Sampler,
+    texture::{SolidColor, Texture},
+};
+use std::sync::Arc;
 
 pub trait Scatterable {
-    fn scatter(&self, ray: &Ray, hit_record: &Intersection) -> Option<(Ray, Color)>;
+    fn scatter(
+        &self,
+        ray: &Ray,
+        hit_record: &Intersection,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)>;
 }
-#[derive(Clone, Copy)]
+// Clones share their textures
+#[derive(Clone)]
 pub enum Material {
     Matte(Matte),
     Metal(Metal),
+    Dielectric(Dielectric),
     Light(Light),
 }
 impl Material {
@@ -14,87 +30,258 @@ impl Material {
         match self {
             Material::Matte(_) => 1.,
             Material::Metal(_) => 1.,
+            Material::Dielectric(_) => 1.,
             Material::Light(l) => l.intensity,
         }
     }
-    #[allow(dead_code)]
+    // adds surface detail, lights stay flat
+    pub fn with_bump(self, bump: Bump) -> Material {
+        match self {
+            Material::Matte(m) => Material::Matte(Matte {
+                bump: Some(bump),
+                ..m
+            }),
+            Material::Metal(m) => Material::Metal(Metal {
+                bump: Some(bump),
+                ..m
+            }),
+            Material::Dielectric(d) => Material::Dielectric(Dielectric {
+                bump: Some(bump),
+                ..d
+            }),
+            Material::Light(l) => Material::Light(l),
+        }
+    }
+    // perturbs the normal of the hit, has to run before `scatter`
+    pub fn apply_bump(&self, intersection: &mut Intersection) {
+        let bump = match self {
+            Material::Matte(m) => &m.bump,
+            Material::Metal(m) => &m.bump,
+            Material::Dielectric(d) => &d.bump,
+            Material::Light(_) => &None,
+        };
+        if let Some(bump) = bump {
+            bump.apply(intersection);
+        }
+    }
+    // Albedo of Lambertian surfaces, the only ones that can reflect light coming from
+    // any direction, which light sampling needs. Metal and glass only send light into
+    // the directions `scatter` picks.
+    pub fn diffuse_color(&self, intersection: &Intersection) -> Option<Color> {
+        match self {
+            Material::Matte(m) => Some(m.texture.value(intersection.uv, &intersection.point)),
+            _ => None,
+        }
+    }
+    // number of the material in the scene file, copies of a material share it
+    pub fn id(&self) -> usize {
+        match self {
+            Material::Matte(m) => m.id,
+            Material::Metal(m) => m.id,
+            Material::Dielectric(d) => d.id,
+            Material::Light(l) => l.id,
+        }
+    }
+    pub fn with_id(self, id: usize) -> Material {
+        match self {
+            Material::Matte(m) => Material::Matte(Matte { id, ..m }),
+            Material::Metal(m) => Material::Metal(Metal { id, ..m }),
+            Material::Dielectric(d) => Material::Dielectric(Dielectric { id, ..d }),
+            Material::Light(l) => Material::Light(Light { id, ..l }),
+        }
+    }
     pub fn matte(color: Color) -> Material {
-        let matte = Matte::new(color);
+        let matte = Matte::new(Arc::new(SolidColor::new(color)));
         Material::Matte(matte)
     }
-    #[allow(dead_code)]
-    pub fn metal() -> Material {
-        let metal = Metal::new();
+    pub fn metal(color: Color, roughness: f64) -> Material {
+        let metal = Metal::new(Arc::new(SolidColor::new(color)), roughness);
         Material::Metal(metal)
     }
+    pub fn light(intensity: f64) -> Material {
+        let light = Light::new(intensity);
+        Material::Light(light)
+    }
+    pub fn dielectric(refraction_index: f64) -> Material {
+        let dielectric = Dielectric::new(refraction_index);
+        Material::Dielectric(dielectric)
+    }
 }
 impl Scatterable for Material {
-    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Option<(Ray, Color)> {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
         match self {
-            Material::Matte(m) => m.scatter(ray, intersection),
-            Material::Metal(m) => m.scatter(ray, intersection),
-            Material::Light(l) => l.scatter(ray, intersection),
+            Material::Matte(m) => m.scatter(ray, intersection, sampler),
+            Material::Metal(m) => m.scatter(ray, intersection, sampler),
+            Material::Dielectric(d) => d.scatter(ray, intersection, sampler),
+            Material::Light(l) => l.scatter(ray, intersection, sampler),
         }
     }
 }
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Metal {
-    color: Color,
+    // tints the reflection
+    texture: Arc<dyn Texture>,
+    // 0 is a perfect mirror, 1 is very blurry (brushed) reflection
+    roughness: f64,
+    bump: Option<Bump>,
+    id: usize,
 }
 
 impl Metal {
-    pub fn new() -> Self {
-        let color = Color::white();
-        Self { color }
-    }
-    fn reflect(v: &Point3D, n: &Point3D) -> Point3D {
-        *v - *n * 2. * v.dot(n)
+    pub fn new(texture: Arc<dyn Texture>, roughness: f64) -> Self {
+        // the scene parser rejects other values, this guards callers building materials in code
+        let roughness = roughness.clamp(0., 1.);
+        Self {
+            texture,
+            roughness,
+            bump: None,
+            id: 0,
+        }
     }
 }
 impl Scatterable for Metal {
-    fn scatter(&self, ray: &Ray, intersection: &Intersection) -> Option<(Ray, Color)> {
-        let reflected = Metal::reflect(&ray.direction.unit_vector(), &intersection.normal);
-        let scattered = Ray::new(intersection.point, reflected);
+    fn scatter(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let reflected = reflect(&ray.direction.unit_vector(), &intersection.normal);
+        // perturb the mirror direction inside a sphere around its tip
+        let fuzz = if self.roughness > 0. {
+            Point3D::in_unit_sphere(sampler.next_2d(), sampler.next_1d()) * self.roughness
+        } else {
+            Point3D::new(0., 0., 0.)
+        };
+        let scattered = Ray::new(intersection.point, reflected + fuzz);
         if scattered.direction.dot(&intersection.normal) < 0.0 {
             return None;
         }
-        Some((scattered, self.color.clone()))
+        let color = self.texture.value(intersection.uv, &intersection.point);
+        Some((scattered, color))
     }
 }
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Matte {
-    color: Color,
+    texture: Arc<dyn Texture>,
+    bump: Option<Bump>,
+    id: usize,
 }
 
 impl Matte {
-    pub fn new(color: Color) -> Self {
-        Self { color }
+    pub fn new(texture: Arc<dyn Texture>) -> Self {
+        Self {
+            texture,
+            bump: None,
+            id: 0,
+        }
     }
 }
 
 impl Scatterable for Matte {
-    fn scatter(&self, _ray: &Ray, intersection: &Intersection) -> Option<(Ray, Color)> {
-        let mut scatter_direction = intersection.normal + Point3D::random_unit_vector();
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        intersection: &Intersection,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        let mut scatter_direction = intersection.normal + Point3D::on_unit_sphere(sampler.next_2d());
         if scatter_direction.near_zero() {
             scatter_direction = intersection.normal;
         }
         let target = intersection.point + scatter_direction;
         let bounced_ray = Ray::new(intersection.point, target - intersection.point);
-        Some((bounced_ray, self.color.clone()))
+        let color = self.texture.value(intersection.uv, &intersection.point);
+        Some((bounced_ray, color))
+    }
+}
+// Transparent material such as glass or water
+#[derive(Clone)]
+pub struct Dielectric {
+    refraction_index: f64,
+    bump: Option<Bump>,
+    id: usize,
+}
+
+impl Dielectric {
+    pub fn new(refraction_index: f64) -> Self {
+        Self {
+            refraction_index,
+            bump: None,
+            id: 0,
+        }
+    }
+    // Schlick's approximation of the Fresnel reflectance
+    fn reflectance(cosine: f64, refraction_ratio: f64) -> f64 {
+        let r0 = ((1. - refraction_ratio) / (1. + refraction_ratio)).powi(2);
+        r0 + (1. - r0) * (1. - cosine).powi(5)
     }
 }
+
+impl Scatterable for Dielectric {
+    fn scatter(
+        &self,
+        ray: &Ray,
+        intersection: &Intersection,
+        sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
+        // entering the material from outside or leaving it back into the air
+        let refraction_ratio = if intersection.front_face {
+            1. / self.refraction_index
+        } else {
+            self.refraction_index
+        };
+        let direction = ray.direction.unit_vector();
+        let cos_theta = (direction * -1.).dot(&intersection.normal).min(1.);
+        let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+
+        let total_internal_reflection = refraction_ratio * sin_theta > 1.;
+        let scattered = if total_internal_reflection
+            || Dielectric::reflectance(cos_theta, refraction_ratio) > sampler.next_1d()
+        {
+            reflect(&direction, &intersection.normal)
+        } else {
+            refract(&direction, &intersection.normal, refraction_ratio)
+        };
+        Some((Ray::new(intersection.point, scattered), Color::white()))
+    }
+}
+
+fn reflect(v: &Point3D, n: &Point3D) -> Point3D {
+    *v - *n * 2. * v.dot(n)
+}
+
+// Snell's law, `v` and `n` are unit vectors on opposite sides of the surface
+fn refract(v: &Point3D, n: &Point3D, refraction_ratio: f64) -> Point3D {
+    let cos_theta = (*v * -1.).dot(n).min(1.);
+    let perpendicular = (*v + *n * cos_theta) * refraction_ratio;
+    let parallel = *n * -(1. - perpendicular.length_squared()).abs().sqrt();
+    perpendicular + parallel
+}
+
 #[derive(Clone, Copy)]
 pub struct Light {
     intensity: f64,
+    id: usize,
 }
 impl Light {
     pub fn new(intensity: f64) -> Self {
-        Self { intensity }
+        Self { intensity, id: 0 }
     }
 }
 
 impl Scatterable for Light {
-    fn scatter(&self, _ray: &Ray, _intersection: &Intersection) -> Option<(Ray, Color)> {
+    fn scatter(
+        &self,
+        _ray: &Ray,
+        _intersection: &Intersection,
+        _sampler: &mut dyn Sampler,
+    ) -> Option<(Ray, Color)> {
         None
     }
 }
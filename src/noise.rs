@@ -0,0 +1,110 @@
+use crate::{point3d::Point3D, random::stream_seed};
+
+// Ken Perlin's improved gradient noise. The permutation only depends on the
+// seed, so procedural textures look the same in every render.
+#[derive(Clone)]
+pub struct Perlin {
+    // the permutation twice in a row, so lookups never have to wrap around
+    permutation: [u8; 512],
+}
+
+impl Perlin {
+    pub fn new(seed: u64) -> Self {
+        let mut shuffled: Vec<u8> = (0..=255).collect();
+        // Fisher-Yates shuffle
+        for i in (1..256).rev() {
+            let j = (stream_seed(seed, i as u64) % (i as u64 + 1)) as usize;
+            shuffled.swap(i, j);
+        }
+        let mut permutation = [0; 512];
+        for (i, value) in permutation.iter_mut().enumerate() {
+            *value = shuffled[i % 256];
+        }
+        Self { permutation }
+    }
+    // smooth noise in range -1 - 1, it is 0 at every whole number coordinate
+    pub fn noise(&self, p: &Point3D) -> f64 {
+        let (x, y, z) = (p.x().floor(), p.y().floor(), p.z().floor());
+        let (fx, fy, fz) = (p.x() - x, p.y() - y, p.z() - z);
+        let (xi, yi, zi) = (
+            (x as i64).rem_euclid(256) as usize,
+            (y as i64).rem_euclid(256) as usize,
+            (z as i64).rem_euclid(256) as usize,
+        );
+        let perm = &self.permutation;
+        let hash = |dx: usize, dy: usize, dz: usize| {
+            perm[perm[perm[xi + dx] as usize + yi + dy] as usize + zi + dz]
+        };
+        let (u, v, w) = (fade(fx), fade(fy), fade(fz));
+        let lerp = |t: f64, a: f64, b: f64| a + t * (b - a);
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, gradient(hash(0, 0, 0), fx, fy, fz), gradient(hash(1, 0, 0), fx - 1., fy, fz)),
+                lerp(
+                    u,
+                    gradient(hash(0, 1, 0), fx, fy - 1., fz),
+                    gradient(hash(1, 1, 0), fx - 1., fy - 1., fz),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    gradient(hash(0, 0, 1), fx, fy, fz - 1.),
+                    gradient(hash(1, 0, 1), fx - 1., fy, fz - 1.),
+                ),
+                lerp(
+                    u,
+                    gradient(hash(0, 1, 1), fx, fy - 1., fz - 1.),
+                    gradient(hash(1, 1, 1), fx - 1., fy - 1., fz - 1.),
+                ),
+            ),
+        )
+    }
+    // fractal Brownian motion: every octave adds noise at twice the frequency and half the strength
+    pub fn fbm(&self, p: &Point3D, octaves: usize) -> f64 {
+        self.octaves(p, octaves, |n| n)
+    }
+    // like fbm, but adding up the absolute noise, which leaves sharp creases where it crosses 0
+    pub fn turbulence(&self, p: &Point3D, octaves: usize) -> f64 {
+        self.octaves(p, octaves, f64::abs)
+    }
+    fn octaves(&self, p: &Point3D, octaves: usize, shape: impl Fn(f64) -> f64) -> f64 {
+        let mut sum = 0.;
+        let mut frequency = 1.;
+        let mut amplitude = 1.;
+        for _ in 0..octaves {
+            sum += amplitude * shape(self.noise(&(*p * frequency)));
+            frequency *= 2.;
+            amplitude *= 0.5;
+        }
+        sum
+    }
+}
+
+impl Default for Perlin {
+    fn default() -> Self {
+        Perlin::new(0)
+    }
+}
+
+// 6t^5 - 15t^4 + 10t^3, its first and second derivatives are 0 at both ends
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+// dot product with one of the 12 directions to the edges of a cube, picked by the hash
+fn gradient(hash: u8, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
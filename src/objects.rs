@@ -1,26 +1,63 @@
+pub mod bvh;
+pub mod cone;
 pub mod cube;
 pub mod cylinder;
+pub mod mesh;
 pub mod plane;
 pub mod sphere;
+pub mod transformed;
+pub mod triangle;
 
+pub use bvh::*;
+pub use cone::*;
 pub use cube::*;
 pub use cylinder::*;
+pub use mesh::*;
 pub use plane::*;
 pub use sphere::*;
+pub use transformed::*;
+pub use triangle::*;
 
-use crate::{material::Material, point3d::Point3D, ray::Ray};
-pub trait Hittable {
+use crate::{aabb::Aabb, material::Material, point3d::Point3D, ray::Ray};
+// objects are shared between the render threads
+pub trait Hittable: Send + Sync {
     fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool;
+    // None for objects that extend to infinity
+    fn bounding_box(&self) -> Option<Aabb>;
+}
+
+impl Hittable for Box<dyn Hittable> {
+    fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
+        self.as_ref().hit(ray, hit_record)
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.as_ref().bounding_box()
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct Intersection <'a>{
     pub point: Point3D,
     pub normal: Point3D,
+    // surface coordinates for textures, usually in range 0 - 1
+    pub uv: (f64, f64),
+    // how the point moves when u or v grow, they span the tangent plane for normal maps.
+    // dpdu x dpdv points the same way as the outward normal, unless a triangle's
+    // texture coordinates are mirrored.
+    pub dpdu: Point3D,
+    pub dpdv: Point3D,
     pub t: f64,
     pub material: Option<&'a Material>,
     pub t_min:f64,
     pub hit_anything: bool,
+    // true when the ray hits the outside of the surface, `normal` always faces the ray
+    pub front_face: bool,
+}
+
+impl Default for Intersection<'_> {
+    fn default() -> Self {
+        Intersection::new()
+    }
 }
 
 impl <'a>Intersection<'a>{
@@ -28,10 +65,160 @@ impl <'a>Intersection<'a>{
         Intersection {
             point: Point3D::new(0., 0., 0.),
             normal: Point3D::new(0., 0., 0.),
+            uv: (0., 0.),
+            dpdu: Point3D::new(0., 0., 0.),
+            dpdv: Point3D::new(0., 0., 0.),
             t: f64::MAX,
             material: None,
             hit_anything: false,
             t_min: 0.001,
+            front_face: true,
         }
     }
+    pub fn set_face_normal(&mut self, ray: &Ray, outward_normal: Point3D) {
+        self.front_face = ray.direction.dot(&outward_normal) < 0.0;
+        self.normal = if self.front_face {
+            outward_normal
+        } else {
+            outward_normal * -1.
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn matte() -> Material {
+        Material::matte(Color::white())
+    }
+
+    // uv of the hit by a ray from `origin` straight towards `target`
+    fn uv(object: &dyn Hittable, origin: Point3D, target: Point3D) -> (f64, f64) {
+        let ray = Ray::new(origin, target - origin);
+        let mut hit = Intersection::new();
+        assert!(object.hit(&ray, &mut hit), "ray towards {target:?} missed");
+        hit.uv
+    }
+
+    fn assert_uv(actual: (f64, f64), expected: (f64, f64)) {
+        let close = (actual.0 - expected.0).abs() < 1e-9 && (actual.1 - expected.1).abs() < 1e-9;
+        assert!(close, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn sphere_uv_is_longitude_and_latitude() {
+        let sphere = Sphere::new(Point3D::new(0., 0., 0.), 1., matte());
+        let p = |x, y, z| Point3D::new(x, y, z);
+        assert_uv(uv(&sphere, p(5., 0., 0.), p(1., 0., 0.)), (0.5, 0.5));
+        assert_uv(uv(&sphere, p(0., 0., 5.), p(0., 0., 1.)), (0.25, 0.5));
+        assert_uv(uv(&sphere, p(0., 5., 0.), p(0., 1., 0.)), (0.5, 1.));
+    }
+
+    #[test]
+    fn plane_uv_is_in_scene_units() {
+        let normal = Point3D::new(0., 1., 0.);
+        let plane = Plane::with_material(Point3D::new(0., -1., 0.), normal, matte());
+        let (u_axis, v_axis) = normal.orthonormal_basis();
+        let target = Point3D::new(0., -1., 0.) + u_axis * 2. + v_axis * 3.;
+        assert_uv(uv(&plane, target + normal * 5., target), (2., 3.));
+    }
+
+    #[test]
+    fn cube_faces_span_the_unit_square() {
+        let cube = Cube::new(Point3D::new(0., 0., 0.), Point3D::new(2., 2., 2.), matte());
+        let p = |x, y, z| Point3D::new(x, y, z);
+        assert_uv(uv(&cube, p(0.5, 1.5, 5.), p(0.5, 1.5, 2.)), (0.25, 0.75));
+        assert_uv(uv(&cube, p(5., 0.5, 1.5), p(2., 0.5, 1.5)), (0.25, 0.25));
+        assert_uv(uv(&cube, p(0.5, 5., 0.5), p(0.5, 2., 0.5)), (0.25, 0.75));
+    }
+
+    #[test]
+    fn cylinder_and_cone_uv() {
+        let base = Point3D::new(0., 0., 0.);
+        let top = Point3D::new(0., 2., 0.);
+        let cylinder = Cylinder::between(base, top, 1., matte());
+        // v runs from the base to the top, the caps center on the middle of the square
+        let (u, v) = uv(&cylinder, Point3D::new(0., 0.5, 5.), Point3D::new(0., 0.5, 1.));
+        assert!((0. ..=1.).contains(&u));
+        assert!((v - 0.25).abs() < 1e-9);
+        assert_uv(uv(&cylinder, Point3D::new(0., 5., 0.), top), (0.5, 0.5));
+        assert_uv(uv(&cylinder, Point3D::new(0., -5., 0.), base), (0.5, 0.5));
+
+        let cone = Cone::new(base, top, 1., 0., matte());
+        let (_, v) = uv(&cone, Point3D::new(0., 1., 5.), Point3D::new(0., 1., 0.5));
+        assert!((v - 0.5).abs() < 1e-9);
+        assert_uv(uv(&cone, Point3D::new(0., -5., 0.), base), (0.5, 0.5));
+    }
+
+    #[test]
+    fn triangle_uv_interpolates_the_vertices() {
+        let vertices = [Point3D::new(0., 0., 0.), Point3D::new(1., 0., 0.), Point3D::new(0., 1., 0.)];
+        let target = Point3D::new(0.25, 0.5, 0.);
+        let origin = target + Point3D::new(0., 0., 5.);
+        // barycentric weights of the second and third vertex without texture coordinates
+        let plain = Triangle::new(vertices[0], vertices[1], vertices[2], matte());
+        assert_uv(uv(&plain, origin, target), (0.25, 0.5));
+        let uvs = [(0., 0.), (2., 0.), (0., 4.)];
+        let textured = Triangle::with_attributes(vertices, None, Some(uvs), matte());
+        assert_uv(uv(&textured, origin, target), (0.5, 2.));
+    }
+
+    // dpdu and dpdv lie in the surface and dpdu x dpdv points out of it
+    fn assert_tangents(object: &dyn Hittable, origin: Point3D, target: Point3D) {
+        let ray = Ray::new(origin, target - origin);
+        let mut hit = Intersection::new();
+        assert!(object.hit(&ray, &mut hit), "ray towards {target:?} missed");
+        let outward = if hit.front_face { hit.normal } else { hit.normal * -1. };
+        let (dpdu, dpdv) = (hit.dpdu.unit_vector(), hit.dpdv.unit_vector());
+        assert!(dpdu.dot(&outward).abs() < 1e-9, "dpdu {dpdu:?} leaves the surface at {target:?}");
+        assert!(dpdv.dot(&outward).abs() < 1e-9, "dpdv {dpdv:?} leaves the surface at {target:?}");
+        assert!(dpdu.cross(&dpdv).dot(&outward) > 0., "tangents are mirrored at {target:?}");
+    }
+
+    #[test]
+    fn tangents_of_every_primitive() {
+        let p = |x, y, z| Point3D::new(x, y, z);
+        let sphere = Sphere::new(p(0., 0., 0.), 1., matte());
+        assert_tangents(&sphere, p(3., 2., 4.), p(0.6, 0.48, 0.64));
+
+        let plane = Plane::with_material(p(0., 0., 0.), p(0., 1., 0.), matte());
+        assert_tangents(&plane, p(1., 5., 2.), p(0.5, 0., -0.3));
+
+        let cube = Cube::new(p(0., 0., 0.), p(2., 2., 2.), matte());
+        for (origin, target) in [
+            (p(5., 1., 1.), p(2., 1., 1.)),
+            (p(-5., 1., 1.), p(0., 1., 1.)),
+            (p(1., 5., 1.), p(1., 2., 1.)),
+            (p(1., -5., 1.), p(1., 0., 1.)),
+            (p(1., 1., 5.), p(1., 1., 2.)),
+            (p(1., 1., -5.), p(1., 1., 0.)),
+        ] {
+            assert_tangents(&cube, origin, target);
+        }
+
+        let base = p(0., 0., 0.);
+        let top = p(1., 2., 0.5);
+        let axis = (top - base).unit_vector();
+        let (side, _) = axis.orthonormal_basis();
+        let middle = (base + top) * 0.5;
+        let cylinder = Cylinder::between(base, top, 0.5, matte());
+        assert_tangents(&cylinder, middle + side * 5., middle + side * 0.5);
+        assert_tangents(&cylinder, top + axis * 5., top);
+        assert_tangents(&cylinder, base - axis * 5., base);
+
+        let cone = Cone::new(base, top, 1., 0.4, matte());
+        assert_tangents(&cone, middle + side * 5., middle + side * 0.7);
+        assert_tangents(&cone, top + axis * 5., top);
+        assert_tangents(&cone, base - axis * 5., base);
+
+        let vertices = [p(0., 0., 0.), p(1., 0., 0.), p(0., 1., 0.)];
+        let uvs = [(0., 0.), (2., 0.5), (0.5, 1.)];
+        let triangle = Triangle::with_attributes(vertices, None, Some(uvs), matte());
+        assert_tangents(&triangle, p(0.25, 0.25, 5.), p(0.25, 0.25, 0.));
+        assert_tangents(&triangle, p(0.25, 0.25, -5.), p(0.25, 0.25, 0.));
+        let plain = Triangle::new(vertices[0], vertices[1], vertices[2], matte());
+        assert_tangents(&plain, p(0.25, 0.25, 5.), p(0.25, 0.25, 0.));
+    }
 }
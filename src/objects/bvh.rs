@@ -0,0 +1,224 @@
+use crate::{aabb::Aabb, point3d::Point3D, ray::Ray};
+
+use super::{Hittable, Intersection};
+
+const BINS: usize = 12;
+const MAX_LEAF_SIZE: usize = 4;
+// cost of visiting a node relative to one primitive intersection
+const TRAVERSAL_COST: f64 = 0.125;
+// deeper nodes become leaves, so traversal fits a fixed size stack
+const MAX_DEPTH: usize = 63;
+
+// Bounding volume hierarchy built with the surface area heuristic.
+// Objects without a bounding box (planes) are kept aside and always tested.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    objects: Vec<Box<dyn Hittable>>,
+    unbounded: Vec<Box<dyn Hittable>>,
+}
+
+struct Node {
+    bounds: Aabb,
+    kind: NodeKind,
+}
+
+enum NodeKind {
+    // objects[start..start + count]
+    Leaf { start: usize, count: usize },
+    // the first child directly follows its parent in `nodes`
+    Interior { second_child: usize, axis: usize },
+}
+
+struct Primitive {
+    index: usize,
+    bounds: Aabb,
+    centroid: Point3D,
+}
+
+impl Bvh {
+    pub fn new(objects: Vec<Box<dyn Hittable>>) -> Self {
+        let mut bounded = vec![];
+        let mut unbounded = vec![];
+        let mut primitives = vec![];
+        for object in objects {
+            match object.bounding_box() {
+                Some(bounds) => {
+                    primitives.push(Primitive {
+                        index: bounded.len(),
+                        bounds,
+                        centroid: bounds.centroid(),
+                    });
+                    bounded.push(Some(object));
+                }
+                None => unbounded.push(object),
+            }
+        }
+
+        let mut nodes = vec![];
+        if !primitives.is_empty() {
+            build(&mut nodes, &mut primitives, 0, 0);
+        }
+        // reorder objects so every leaf references a contiguous range
+        let objects = primitives
+            .iter()
+            .map(|p| bounded[p.index].take().unwrap())
+            .collect();
+
+        Self {
+            nodes,
+            objects,
+            unbounded,
+        }
+    }
+}
+
+fn build(nodes: &mut Vec<Node>, primitives: &mut [Primitive], start: usize, depth: usize) -> usize {
+    let bounds = primitives
+        .iter()
+        .fold(Aabb::empty(), |b, p| b.union(&p.bounds));
+    let node_index = nodes.len();
+    let leaf = Node {
+        bounds,
+        kind: NodeKind::Leaf {
+            start,
+            count: primitives.len(),
+        },
+    };
+    nodes.push(leaf);
+    if primitives.len() == 1 || depth == MAX_DEPTH {
+        return node_index;
+    }
+
+    let centroid_bounds = primitives
+        .iter()
+        .fold(Aabb::empty(), |b, p| b.grow(&p.centroid));
+    let axis = centroid_bounds.longest_axis();
+    let low = centroid_bounds.min.axis(axis);
+    let extent = centroid_bounds.max.axis(axis) - low;
+    if extent <= 0. {
+        // all centroids coincide, no split can separate them
+        return node_index;
+    }
+
+    let bin_of = |p: &Primitive| {
+        let bin = ((p.centroid.axis(axis) - low) / extent * BINS as f64) as usize;
+        bin.min(BINS - 1)
+    };
+    let mut bin_bounds = [Aabb::empty(); BINS];
+    let mut bin_counts = [0usize; BINS];
+    for p in primitives.iter() {
+        let bin = bin_of(p);
+        bin_bounds[bin] = bin_bounds[bin].union(&p.bounds);
+        bin_counts[bin] += 1;
+    }
+
+    // cost of splitting after bin i, sweeping from both sides
+    let mut best_split = 0;
+    let mut best_cost = f64::INFINITY;
+    for split in 0..BINS - 1 {
+        let (left, left_count) = (0..=split).fold((Aabb::empty(), 0), |(b, n), i| {
+            (b.union(&bin_bounds[i]), n + bin_counts[i])
+        });
+        let (right, right_count) = (split + 1..BINS).fold((Aabb::empty(), 0), |(b, n), i| {
+            (b.union(&bin_bounds[i]), n + bin_counts[i])
+        });
+        if left_count == 0 || right_count == 0 {
+            continue;
+        }
+        let cost = TRAVERSAL_COST
+            + (left.surface_area() * left_count as f64 + right.surface_area() * right_count as f64)
+                / bounds.surface_area();
+        if cost < best_cost {
+            best_cost = cost;
+            best_split = split;
+        }
+    }
+
+    let leaf_cost = primitives.len() as f64;
+    if primitives.len() <= MAX_LEAF_SIZE && leaf_cost <= best_cost {
+        return node_index;
+    }
+
+    let mid = partition(primitives, |p| bin_of(p) <= best_split);
+    let (left, right) = primitives.split_at_mut(mid);
+    build(nodes, left, start, depth + 1);
+    let second_child = build(nodes, right, start + mid, depth + 1);
+    nodes[node_index].kind = NodeKind::Interior { second_child, axis };
+    node_index
+}
+
+// moves the elements matching `predicate` to the front, returns their count
+fn partition<F: Fn(&Primitive) -> bool>(primitives: &mut [Primitive], predicate: F) -> usize {
+    let mut mid = 0;
+    for i in 0..primitives.len() {
+        if predicate(&primitives[i]) {
+            primitives.swap(i, mid);
+            mid += 1;
+        }
+    }
+    mid
+}
+
+impl Bvh {
+    // like `hit`, also returns the number of bounding boxes and objects the ray was tested against
+    pub fn hit_with_cost<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> (bool, usize) {
+        let mut hit_anything = false;
+        let mut cost = self.unbounded.len();
+        for object in &self.unbounded {
+            hit_anything |= object.hit(ray, hit_record);
+        }
+        if self.nodes.is_empty() {
+            return (hit_anything, cost);
+        }
+
+        let inv_direction = Point3D::new(
+            1. / ray.direction.x(),
+            1. / ray.direction.y(),
+            1. / ray.direction.z(),
+        );
+        // every level adds at most one entry
+        let mut stack = [0; MAX_DEPTH + 1];
+        let mut stack_size = 1;
+        while stack_size > 0 {
+            stack_size -= 1;
+            let index = stack[stack_size];
+            let node = &self.nodes[index];
+            cost += 1;
+            if !node.bounds.hit(ray, &inv_direction, hit_record.t_min, hit_record.t) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, count } => {
+                    cost += count;
+                    for object in &self.objects[start..start + count] {
+                        hit_anything |= object.hit(ray, hit_record);
+                    }
+                }
+                NodeKind::Interior { second_child, axis } => {
+                    // visit the nearer child first so the farther one can be culled
+                    let (near, far) = if ray.direction.axis(axis) < 0. {
+                        (second_child, index + 1)
+                    } else {
+                        (index + 1, second_child)
+                    };
+                    stack[stack_size] = far;
+                    stack[stack_size + 1] = near;
+                    stack_size += 2;
+                }
+            }
+        }
+        (hit_anything, cost)
+    }
+}
+
+impl Hittable for Bvh {
+    fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
+        self.hit_with_cost(ray, hit_record).0
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|root| root.bounds)
+    }
+}
@@ -0,0 +1,175 @@
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::point3d::Point3D;
+use crate::ray::Ray;
+
+use super::cylinder::{body_surface, cap_surface, closest, disk_bounds, disk_hit};
+use super::{Hittable, Intersection};
+
+// Closed cone between two end points. With a top radius above 0 the tip is
+// cut off, which gives a truncated cone (frustum), e.g. a lampshade or a bucket.
+pub struct Cone {
+    pub material: Material,
+    pub base: Point3D,
+    // unit vector from the base towards the top
+    axis: Point3D,
+    pub height: f64,
+    pub base_radius: f64,
+    pub top_radius: f64,
+}
+
+impl Cone {
+    pub fn new(
+        base: Point3D,
+        top: Point3D,
+        base_radius: f64,
+        top_radius: f64,
+        material: Material,
+    ) -> Self {
+        let height = (top - base).length();
+        Self {
+            base,
+            axis: (top - base) / height,
+            height,
+            base_radius,
+            top_radius,
+            material,
+        }
+    }
+    fn intersect_body(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Point3D)> {
+        // the radius grows by `slope` for every unit along the axis
+        let slope = (self.top_radius - self.base_radius) / self.height;
+        let oc = ray.origin - self.base;
+        let d_a = ray.direction.dot(&self.axis);
+        let oc_a = oc.dot(&self.axis);
+        // radius at the ray origin's height
+        let r = self.base_radius + slope * oc_a;
+
+        // distance from the axis squared == radius squared, both quadratic in t
+        let a = ray.direction.length_squared() - (1. + slope * slope) * d_a * d_a;
+        let half_b = oc.dot(&ray.direction) - oc_a * d_a - r * slope * d_a;
+        let c = oc.length_squared() - oc_a * oc_a - r * r;
+
+        let roots = if a.abs() < 1e-12 {
+            // the ray runs parallel to the side of the cone, there is a single crossing
+            if half_b == 0. {
+                return None;
+            }
+            [-c / (2. * half_b), f64::INFINITY]
+        } else {
+            let disc = half_b * half_b - a * c;
+            if disc < 0. {
+                return None;
+            }
+            let sqrtd = disc.sqrt();
+            let (t1, t2) = ((-half_b - sqrtd) / a, (-half_b + sqrtd) / a);
+            [t1.min(t2), t1.max(t2)]
+        };
+        for t in roots {
+            if t < t_min || t_max < t {
+                continue;
+            }
+            let q = oc + ray.direction * t;
+            let m = q.dot(&self.axis);
+            // also rejects the mirrored cone beyond the tip
+            if m < 0. || m > self.height {
+                continue;
+            }
+            let radial = q - self.axis * m;
+            let normal = if radial.length_squared() > 0. {
+                // tilted against the axis as much as the side slopes
+                (radial.unit_vector() - self.axis * slope).unit_vector()
+            } else {
+                self.axis
+            };
+            return Some((t, normal));
+        }
+        None
+    }
+}
+
+impl Hittable for Cone {
+    fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
+        let top = self.base + self.axis * self.height;
+        let (t_min, t_max) = (hit_record.t_min, hit_record.t);
+        let candidates = [
+            self.intersect_body(ray, t_min, t_max),
+            disk_hit(ray, top, self.axis, self.top_radius, t_min, t_max),
+            disk_hit(ray, self.base, self.axis * -1., self.base_radius, t_min, t_max),
+        ];
+        let Some((part, (t, normal))) = closest(candidates) else {
+            return false;
+        };
+        hit_record.point = ray.at(t);
+        hit_record.set_face_normal(ray, normal);
+        let local = hit_record.point - self.base;
+        let radius_change = self.top_radius - self.base_radius;
+        (hit_record.uv, hit_record.dpdu, hit_record.dpdv) = match part {
+            0 => body_surface(&local, &self.axis, self.height, radius_change),
+            1 => cap_surface(&local, &self.axis, &normal, self.top_radius),
+            _ => cap_surface(&local, &self.axis, &normal, self.base_radius),
+        };
+        hit_record.t = t;
+        hit_record.material = Some(&self.material);
+        hit_record.hit_anything = true;
+        true
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let top = self.base + self.axis * self.height;
+        let base_bounds = disk_bounds(self.base, &self.axis, self.base_radius);
+        Some(base_bounds.union(&disk_bounds(top, &self.axis, self.top_radius)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn hit(cone: &Cone, origin: Point3D, direction: Point3D) -> Option<(f64, Point3D, Point3D)> {
+        let ray = Ray::new(origin, direction);
+        let mut hit = Intersection::new();
+        cone.hit(&ray, &mut hit).then_some((hit.t, hit.point, hit.normal))
+    }
+
+    fn assert_close(actual: Point3D, expected: Point3D) {
+        assert!((actual - expected).length() < 1e-9, "{actual:?} != {expected:?}");
+    }
+
+    fn cone(top_radius: f64) -> Cone {
+        let material = Material::matte(Color::white());
+        Cone::new(Point3D::new(0., 0., 0.), Point3D::new(0., 2., 0.), 1., top_radius, material)
+    }
+
+    #[test]
+    fn side_normal_tilts_with_the_slope() {
+        // the radius is 0.5 half way up
+        let (t, point, normal) = hit(&cone(0.), Point3D::new(0.5, 5., 0.), Point3D::new(0., -1., 0.)).unwrap();
+        assert!((t - 4.).abs() < 1e-9);
+        assert_close(point, Point3D::new(0.5, 1., 0.));
+        assert_close(normal, Point3D::new(1., 0.5, 0.).unit_vector());
+    }
+
+    #[test]
+    fn ray_from_below_hits_the_base() {
+        let (t, _, normal) = hit(&cone(0.), Point3D::new(0.5, -5., 0.), Point3D::new(0., 1., 0.)).unwrap();
+        assert!((t - 5.).abs() < 1e-9);
+        assert_close(normal, Point3D::new(0., -1., 0.));
+    }
+
+    #[test]
+    fn mirrored_cone_beyond_the_tip_is_ignored() {
+        assert!(hit(&cone(0.), Point3D::new(0.5, 5., 0.), Point3D::new(0., 0., 1.)).is_none());
+        assert!(hit(&cone(0.), Point3D::new(-5., 3., 0.), Point3D::new(1., 0., 0.)).is_none());
+    }
+
+    #[test]
+    fn frustum_top_cap_is_closer_than_the_side() {
+        let (t, _, normal) = hit(&cone(0.5), Point3D::new(0.25, 5., 0.), Point3D::new(0., -1., 0.)).unwrap();
+        assert!((t - 3.).abs() < 1e-9);
+        assert_close(normal, Point3D::new(0., 1., 0.));
+        // outside of the top cap, the ray reaches the side
+        let (t, _, _) = hit(&cone(0.5), Point3D::new(0.75, 5., 0.), Point3D::new(0., -1., 0.)).unwrap();
+        assert!((t - 4.).abs() < 1e-9);
+    }
+}
@@ -1,5 +1,5 @@
 use super::{Hittable, Intersection};
-use crate::{material::*, point3d::Point3D, ray::Ray};
+use crate::{aabb::Aabb, material::*, point3d::Point3D, ray::Ray};
 
 pub struct Cube {
     pub min: Point3D,
@@ -11,29 +11,55 @@ impl Cube {
     pub fn new(min: Point3D, max: Point3D, material: Material) -> Self {
         Self { min, max, material }
     }
+    // outward normal, corners given in any order
     fn normal(&self, point: &Point3D) -> Point3D {
         const EPSI: f64 = 0.01;
+        let low = self.min.min(&self.max);
+        let high = self.min.max(&self.max);
         let mut normal = Point3D::new(0., 0., 0.);
-        if (point.x() - self.min.x()).abs() < EPSI {
+        if (point.x() - low.x()).abs() < EPSI {
             normal.set_x(-1.)
         };
-        if (point.x() - self.max.x()).abs() < EPSI {
+        if (point.x() - high.x()).abs() < EPSI {
             normal.set_x(1.)
         };
-        if (point.y() - self.min.y()).abs() < EPSI {
+        if (point.y() - low.y()).abs() < EPSI {
             normal.set_y(-1.)
         };
-        if (point.y() - self.max.y()).abs() < EPSI {
+        if (point.y() - high.y()).abs() < EPSI {
             normal.set_y(1.)
         };
-        if (point.z() - self.min.z()).abs() < EPSI {
+        if (point.z() - low.z()).abs() < EPSI {
             normal.set_z(-1.)
         };
-        if (point.z() - self.min.z()).abs() < EPSI {
+        if (point.z() - high.z()).abs() < EPSI {
             normal.set_z(1.)
         };
         normal
     }
+    // Every face spans 0 - 1 and isn't mirrored when seen from outside, v points up
+    // on the sides. Returns the uv with dpdu and dpdv.
+    fn surface(&self, point: &Point3D, normal: &Point3D) -> ((f64, f64), Point3D, Point3D) {
+        let low = self.min.min(&self.max);
+        let size = self.min.max(&self.max) - low;
+        let local = *point - low;
+        let fraction = |axis: usize| local.axis(axis) / size.axis(axis);
+        let up = Point3D::new(0., size.y(), 0.);
+        if normal.x() != 0. {
+            let s = normal.x();
+            let u = if s > 0. { 1. - fraction(2) } else { fraction(2) };
+            ((u, fraction(1)), Point3D::new(0., 0., -s * size.z()), up)
+        } else if normal.y() != 0. {
+            let s = normal.y();
+            let v = if s > 0. { 1. - fraction(2) } else { fraction(2) };
+            let dpdu = Point3D::new(size.x(), 0., 0.);
+            ((fraction(0), v), dpdu, Point3D::new(0., 0., -s * size.z()))
+        } else {
+            let s = normal.z();
+            let u = if s > 0. { fraction(0) } else { 1. - fraction(0) };
+            ((u, fraction(1)), Point3D::new(s * size.x(), 0., 0.), up)
+        }
+    }
 }
 
 impl Hittable for Cube {
@@ -72,23 +98,29 @@ impl Hittable for Cube {
         if t_z_min > t_min {
             t_min = t_z_min
         }
+        if t_z_max < t_max {
+            t_max = t_z_max
+        }
 
-        if t_min < hit_record.t_min || t_min > hit_record.t{
+        // ray starting inside the cube hits it on the way out
+        let t = if t_min < hit_record.t_min { t_max } else { t_min };
+        if t < hit_record.t_min || t > hit_record.t{
             return false;
         }
-        if t_min > hit_record.t{
-            return false
-        }
-        let point = ray.at(t_min);
+        let point = ray.at(t);
         let normal = self.normal(&point);
 
         hit_record.point = point;
-        hit_record.normal = normal;
-        hit_record.t = t_min;
+        hit_record.set_face_normal(ray, normal);
+        (hit_record.uv, hit_record.dpdu, hit_record.dpdv) = self.surface(&point, &normal);
+        hit_record.t = t;
         hit_record.material = Some(&self.material);
         hit_record.hit_anything = true;
 
         true
 
     }
+    fn bounding_box(&self) -> Option<Aabb> {
+        Some(Aabb::new(self.min, self.max))
+    }
 }
@@ -1,125 +1,240 @@
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
 use crate::material::Material;
 use crate::point3d::Point3D;
 use crate::ray::Ray;
 
 use super::{Hittable, Intersection};
+
+// Closed cylinder between two end points
 pub struct Cylinder {
     pub material: Material,
     pub base: Point3D,
+    // unit vector from the base towards the top
     axis: Point3D,
     pub radius: f64,
     pub height: f64,
 }
 
 impl Cylinder {
+    // upright cylinder standing on `base`
     pub fn new(base: Point3D, radius: f64, material: Material, height: f64) -> Self {
+        Cylinder::between(base, base + Point3D::new(0., height, 0.), radius, material)
+    }
+    pub fn between(base: Point3D, top: Point3D, radius: f64, material: Material) -> Self {
+        let height = (top - base).length();
         Self {
             base,
-            axis: Point3D::new(0., 1., 0.),
+            axis: (top - base) / height,
             radius,
             material,
             height,
         }
     }
-    fn check_cap(
-        &self,
-        ray: &Ray,
-        normal: Point3D,
-        center: Point3D,
-        t_min: f64,
-        t_max: f64,
-    ) -> Option<(f64, Point3D, Point3D)> {
-        let d_dot_n = normal.dot(&ray.direction);
-        if d_dot_n == 0.0 {
-            return None;
-        }
-        let t = normal.dot(&(center - ray.origin)) / d_dot_n;
-        if t <= t_min || t >= t_max {
-            return None;
-        }
-        let point = ray.at(t);
-        if (point.x() - center.x()).powf(2.0) + (point.z() - center.z()).powf(2.0)
-            > self.radius.powf(2.0)
-        {
-            return None;
-        }
-        Some((t, normal, point))
-    }
-    fn intersect_caps(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Point3D, Point3D)> {
-        let mut normal = Point3D::new(0., 1., 0.);
-        // top
-        let center = self.base + Point3D::new(0., self.height, 0.);
-        let intersect = self.check_cap(ray, normal, center, t_min, t_max);
-        if intersect.is_some() {
-            return Some(intersect.unwrap());
-        }
-        // bottom
-        normal.set_y(-1.);
-        let intersect = self.check_cap(ray, normal, self.base, t_min, t_max);
-        if intersect.is_some() {
-            return Some(intersect.unwrap());
-        }
-        None
-    }
-    fn intersect_body(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Point3D, Point3D)> {
+    fn intersect_body(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, Point3D)> {
+        // only the parts perpendicular to the axis matter for the distance from it
         let oc = ray.origin - self.base;
-        let a = (ray.direction.x().powf(2.)) + (ray.direction.z().powf(2.));
-        let b = 2. * (oc.x() * ray.direction.x() + oc.z() * ray.direction.z());
-        let c = oc.x().powf(2.) + oc.z().powf(2.) - self.radius * self.radius;
+        let d_perp = ray.direction - self.axis * ray.direction.dot(&self.axis);
+        let oc_perp = oc - self.axis * oc.dot(&self.axis);
+        let a = d_perp.length_squared();
+        let half_b = d_perp.dot(&oc_perp);
+        let c = oc_perp.length_squared() - self.radius * self.radius;
 
-        let disc = b.powf(2.) - 4. * a * c;
-        if disc < 0.0 {
+        let disc = half_b * half_b - a * c;
+        if a == 0. || disc < 0.0 {
             return None;
         }
         let sqrtd = disc.sqrt();
-        let mut t1 = (-b - sqrtd) / (2. * a);
-        let t2 = (-b + sqrtd) / (2. * a);
-        if t1 > t2 {
-            t1 = t2
-        };
-        if t1 < t_min || t_max < t1 {
-            return None;
-        }
-        let point = ray.at(t1);
-        // turncate
-        if point.y() < self.base.y() || point.y() > self.base.y() + self.height {
-            return None;
+        // the far side is hit when the near one is behind the ray or cut off
+        for t in [(-half_b - sqrtd) / a, (-half_b + sqrtd) / a] {
+            if t < t_min || t_max < t {
+                continue;
+            }
+            let m = (oc + ray.direction * t).dot(&self.axis);
+            if m < 0. || m > self.height {
+                continue;
+            }
+            let normal = (oc + ray.direction * t - self.axis * m) / self.radius;
+            return Some((t, normal));
         }
-        let m = ray.direction.dot(&self.axis) * t1 + oc.dot(&self.axis);
-        let normal = (point - self.base - self.axis * m).unit_vector();
-        Some((t1, normal, point))
+        None
     }
 }
 
 impl Hittable for Cylinder {
     fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
-        let cap_intersect = self.intersect_caps(ray, hit_record.t_min, hit_record.t);
-        if cap_intersect.is_some() {
-            let (cap_t, cap_normal, cap_point) = cap_intersect.unwrap();
-            if cap_t > hit_record.t{
-                return false
-            }
-            hit_record.point = cap_point;
-            hit_record.normal = cap_normal;
-            hit_record.t = cap_t;
-            hit_record.material = Some(&self.material);
-            hit_record.hit_anything = true;
-            return true
-        }
-        let intersect = self.intersect_body(ray, hit_record.t_min, hit_record.t);
-        if intersect.is_none() {
-            return false
-        } else {
-            let (body_t, body_normal, body_point) = intersect.unwrap();
-            if body_t > hit_record.t{
-                return false
-            }
-            hit_record.point = body_point;
-            hit_record.normal = body_normal;
-            hit_record.t = body_t;
-            hit_record.material = Some(&self.material);
-            hit_record.hit_anything = true;
-            return true
-        }
+        let top = self.base + self.axis * self.height;
+        let (t_min, t_max) = (hit_record.t_min, hit_record.t);
+        let candidates = [
+            self.intersect_body(ray, t_min, t_max),
+            disk_hit(ray, top, self.axis, self.radius, t_min, t_max),
+            disk_hit(ray, self.base, self.axis * -1., self.radius, t_min, t_max),
+        ];
+        let Some((part, (t, normal))) = closest(candidates) else {
+            return false;
+        };
+        hit_record.point = ray.at(t);
+        hit_record.set_face_normal(ray, normal);
+        let local = hit_record.point - self.base;
+        (hit_record.uv, hit_record.dpdu, hit_record.dpdv) = match part {
+            0 => body_surface(&local, &self.axis, self.height, 0.),
+            _ => cap_surface(&local, &self.axis, &normal, self.radius),
+        };
+        hit_record.t = t;
+        hit_record.material = Some(&self.material);
+        hit_record.hit_anything = true;
+        true
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let top = self.base + self.axis * self.height;
+        let base_bounds = disk_bounds(self.base, &self.axis, self.radius);
+        Some(base_bounds.union(&disk_bounds(top, &self.axis, self.radius)))
+    }
+}
+
+// the hit with the smallest t and which of the candidates it is, if any
+pub(super) fn closest<const N: usize>(
+    candidates: [Option<(f64, Point3D)>; N],
+) -> Option<(usize, (f64, Point3D))> {
+    candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, hit)| Some((i, hit?)))
+        .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+}
+
+// `local` is the hit relative to the base. u goes once around the axis, v from the base
+// to the top, where the radius has changed by `radius_change`. Returns the uv with dpdu and dpdv.
+pub(super) fn body_surface(
+    local: &Point3D,
+    axis: &Point3D,
+    height: f64,
+    radius_change: f64,
+) -> ((f64, f64), Point3D, Point3D) {
+    let (x_axis, y_axis) = axis.orthonormal_basis();
+    let angle = local.dot(&y_axis).atan2(local.dot(&x_axis)) + PI;
+    let along = local.dot(axis);
+    let radial = *local - *axis * along;
+    let dpdu = axis.cross(&radial) * (2. * PI);
+    let dpdv = if radial.near_zero() {
+        *axis * height
+    } else {
+        *axis * height + radial.unit_vector() * radius_change
+    };
+    ((angle / (2. * PI), along / height), dpdu, dpdv)
+}
+
+// The cap disk fills the unit square, `normal` points out of the solid.
+// The bottom cap is seen from below, flipping u keeps it from being mirrored.
+pub(super) fn cap_surface(
+    local: &Point3D,
+    axis: &Point3D,
+    normal: &Point3D,
+    radius: f64,
+) -> ((f64, f64), Point3D, Point3D) {
+    let (x_axis, y_axis) = axis.orthonormal_basis();
+    let x_axis = if normal.dot(axis) < 0. { x_axis * -1. } else { x_axis };
+    let uv = (
+        0.5 + local.dot(&x_axis) / (2. * radius),
+        0.5 + local.dot(&y_axis) / (2. * radius),
+    );
+    (uv, x_axis * (2. * radius), y_axis * (2. * radius))
+}
+
+// flat disk used for the caps, `normal` is a unit vector pointing out of the solid
+pub(super) fn disk_hit(
+    ray: &Ray,
+    center: Point3D,
+    normal: Point3D,
+    radius: f64,
+    t_min: f64,
+    t_max: f64,
+) -> Option<(f64, Point3D)> {
+    let d_dot_n = normal.dot(&ray.direction);
+    if d_dot_n == 0.0 || radius <= 0. {
+        return None;
+    }
+    let t = normal.dot(&(center - ray.origin)) / d_dot_n;
+    if t <= t_min || t >= t_max {
+        return None;
+    }
+    if (ray.at(t) - center).length_squared() > radius * radius {
+        return None;
+    }
+    Some((t, normal))
+}
+
+// a disk perpendicular to the unit vector `axis` reaches r * sin(angle between axis and x, y or z)
+pub(super) fn disk_bounds(center: Point3D, axis: &Point3D, radius: f64) -> Aabb {
+    let reach = |a: f64| radius * (1. - a * a).max(0.).sqrt();
+    let extent = Point3D::new(reach(axis.x()), reach(axis.y()), reach(axis.z()));
+    Aabb::new(center - extent, center + extent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Color;
+
+    fn hit(cylinder: &Cylinder, origin: Point3D, direction: Point3D) -> Option<(f64, Point3D, Point3D)> {
+        let ray = Ray::new(origin, direction);
+        let mut hit = Intersection::new();
+        cylinder.hit(&ray, &mut hit).then_some((hit.t, hit.point, hit.normal))
+    }
+
+    fn assert_close(actual: Point3D, expected: Point3D) {
+        assert!((actual - expected).length() < 1e-9, "{actual:?} != {expected:?}");
+    }
+
+    fn upright() -> Cylinder {
+        let material = Material::matte(Color::white());
+        Cylinder::between(Point3D::new(0., 0., 0.), Point3D::new(0., 2., 0.), 1., material)
+    }
+
+    #[test]
+    fn ray_along_the_axis_hits_the_cap() {
+        let (t, point, normal) = hit(&upright(), Point3D::new(0.5, 5., 0.), Point3D::new(0., -1., 0.)).unwrap();
+        assert!((t - 3.).abs() < 1e-9);
+        assert_close(point, Point3D::new(0.5, 2., 0.));
+        assert_close(normal, Point3D::new(0., 1., 0.));
+    }
+
+    #[test]
+    fn ray_missing_the_cap_disk_hits_the_body() {
+        // crosses the cap plane outside of the disk, then enters through the side
+        let (t, point, normal) = hit(&upright(), Point3D::new(0., 3., 3.), Point3D::new(0., -1., -1.)).unwrap();
+        assert!((t - 2.).abs() < 1e-9);
+        assert_close(point, Point3D::new(0., 1., 1.));
+        assert_close(normal, Point3D::new(0., 0., 1.));
+    }
+
+    #[test]
+    fn ray_from_below_hits_the_bottom_cap() {
+        let (_, point, normal) = hit(&upright(), Point3D::new(0., -3., 0.), Point3D::new(0., 1., 0.)).unwrap();
+        assert_close(point, Point3D::new(0., 0., 0.));
+        assert_close(normal, Point3D::new(0., -1., 0.));
+    }
+
+    #[test]
+    fn normal_faces_rays_from_inside() {
+        let cylinder = upright();
+        let ray = Ray::new(Point3D::new(0., 1., 0.), Point3D::new(1., 0., 0.));
+        let mut hit = Intersection::new();
+        assert!(cylinder.hit(&ray, &mut hit));
+        assert!(!hit.front_face);
+        assert_close(hit.normal, Point3D::new(-1., 0., 0.));
+    }
+
+    #[test]
+    fn arbitrary_axis() {
+        let material = Material::matte(Color::white());
+        let cylinder = Cylinder::between(Point3D::new(0., 0., 0.), Point3D::new(2., 0., 0.), 0.5, material);
+        let (t, _, normal) = hit(&cylinder, Point3D::new(5., 0., 0.), Point3D::new(-1., 0., 0.)).unwrap();
+        assert!((t - 3.).abs() < 1e-9);
+        assert_close(normal, Point3D::new(1., 0., 0.));
+        let (_, _, normal) = hit(&cylinder, Point3D::new(1., 5., 0.), Point3D::new(0., -1., 0.)).unwrap();
+        assert_close(normal, Point3D::new(0., 1., 0.));
+        assert!(hit(&cylinder, Point3D::new(3., 5., 0.), Point3D::new(0., -1., 0.)).is_none());
     }
 }
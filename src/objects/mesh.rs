@@ -0,0 +1,185 @@
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::point3d::Point3D;
+use crate::ray::Ray;
+use std::{fmt, fs, path::Path};
+
+use super::{Bvh, Hittable, Intersection, Triangle};
+
+// Triangle mesh loaded from a Wavefront OBJ file
+pub struct Mesh {
+    triangles: Bvh,
+}
+
+#[derive(Debug)]
+pub struct ObjError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ObjError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.line == 0 {
+            return write!(f, "{}", self.message);
+        }
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ObjError {}
+
+// one corner of a face: indices into positions, uvs and normals
+#[derive(Clone, Copy)]
+struct Corner {
+    position: usize,
+    uv: Option<usize>,
+    normal: Option<usize>,
+}
+
+impl Mesh {
+    pub fn load(path: &Path, material: Material) -> Result<Self, ObjError> {
+        let source = fs::read_to_string(path).map_err(|e| ObjError {
+            line: 0,
+            message: format!("cannot read {}: {e}", path.display()),
+        })?;
+        Mesh::parse(&source, material)
+    }
+
+    pub fn parse(source: &str, material: Material) -> Result<Self, ObjError> {
+        let mut positions = vec![];
+        let mut uvs = vec![];
+        let mut normals = vec![];
+        let mut triangles: Vec<Box<dyn Hittable>> = vec![];
+
+        for (index, line) in source.lines().enumerate() {
+            let line_no = index + 1;
+            let error = |message: String| ObjError {
+                line: line_no,
+                message,
+            };
+            let line = line.split('#').next().unwrap_or("");
+            let mut fields = line.split_whitespace();
+            let Some(keyword) = fields.next() else {
+                continue;
+            };
+            let fields: Vec<&str> = fields.collect();
+            match keyword {
+                "v" => {
+                    let [x, y, z] = parse_floats(&fields).map_err(error)?;
+                    positions.push(Point3D::new(x, y, z));
+                }
+                "vn" => {
+                    let [x, y, z] = parse_floats(&fields).map_err(error)?;
+                    normals.push(Point3D::new(x, y, z).unit_vector());
+                }
+                "vt" => {
+                    // the optional third coordinate is ignored
+                    let u = parse_float(fields.first().copied()).map_err(error)?;
+                    let v = match fields.get(1) {
+                        Some(v) => parse_float(Some(v)).map_err(error)?,
+                        None => 0.,
+                    };
+                    uvs.push((u, v));
+                }
+                "f" => {
+                    if fields.len() < 3 {
+                        return Err(error("a face needs at least 3 vertices".to_string()));
+                    }
+                    let counts = (positions.len(), uvs.len(), normals.len());
+                    let corners = fields
+                        .iter()
+                        .map(|field| parse_corner(field, counts))
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(error)?;
+                    // fan triangulation, fine for the convex polygons OBJ exporters write
+                    for i in 1..corners.len() - 1 {
+                        let face = [corners[0], corners[i], corners[i + 1]];
+                        let vertices = face.map(|c| positions[c.position]);
+                        let face_normals = if face.iter().all(|c| c.normal.is_some()) {
+                            Some(face.map(|c| normals[c.normal.unwrap()]))
+                        } else {
+                            None
+                        };
+                        let face_uvs = if face.iter().all(|c| c.uv.is_some()) {
+                            Some(face.map(|c| uvs[c.uv.unwrap()]))
+                        } else {
+                            None
+                        };
+                        let triangle =
+                            Triangle::with_attributes(vertices, face_normals, face_uvs, material.clone());
+                        triangles.push(Box::new(triangle));
+                    }
+                }
+                // groups, smoothing groups and material libraries are not supported
+                _ => {}
+            }
+        }
+
+        if triangles.is_empty() {
+            return Err(ObjError {
+                line: 0,
+                message: "mesh has no faces".to_string(),
+            });
+        }
+        Ok(Self {
+            triangles: Bvh::new(triangles),
+        })
+    }
+}
+
+fn parse_float(field: Option<&str>) -> Result<f64, String> {
+    let field = field.ok_or_else(|| "missing coordinate".to_string())?;
+    field
+        .parse::<f64>()
+        .map_err(|_| format!("invalid number `{field}`"))
+}
+
+fn parse_floats(fields: &[&str]) -> Result<[f64; 3], String> {
+    Ok([
+        parse_float(fields.first().copied())?,
+        parse_float(fields.get(1).copied())?,
+        parse_float(fields.get(2).copied())?,
+    ])
+}
+
+// `v`, `v/vt`, `v//vn` or `v/vt/vn`, indices start at 1 and negative ones count from the end
+fn parse_corner(field: &str, counts: (usize, usize, usize)) -> Result<Corner, String> {
+    let mut parts = field.split('/');
+    let position = parse_index(parts.next(), counts.0, field)?
+        .ok_or_else(|| format!("missing vertex index in `{field}`"))?;
+    let uv = parse_index(parts.next(), counts.1, field)?;
+    let normal = parse_index(parts.next(), counts.2, field)?;
+    Ok(Corner {
+        position,
+        uv,
+        normal,
+    })
+}
+
+fn parse_index(part: Option<&str>, count: usize, field: &str) -> Result<Option<usize>, String> {
+    let part = match part {
+        None | Some("") => return Ok(None),
+        Some(part) => part,
+    };
+    let index = part
+        .parse::<i64>()
+        .map_err(|_| format!("invalid index in `{field}`"))?;
+    let resolved = if index > 0 {
+        index - 1
+    } else {
+        count as i64 + index
+    };
+    if index == 0 || resolved < 0 || resolved >= count as i64 {
+        return Err(format!("index out of range in `{field}`"));
+    }
+    Ok(Some(resolved as usize))
+}
+
+impl Hittable for Mesh {
+    fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
+        self.triangles.hit(ray, hit_record)
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        self.triangles.bounding_box()
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{color::Color, material::*, point3d::Point3D, ray::Ray};
+use crate::{aabb::Aabb, color::Color, material::*, point3d::Point3D, ray::Ray};
 
 use super::{Hittable, Intersection};
 
@@ -10,8 +10,14 @@ pub struct Plane {
 
 impl Plane {
     pub fn new(point: Point3D, normal: Point3D) -> Self {
-        let matte = Matte::new(Color::gray());
-        let material = Material::Matte(matte);
+        let material = Material::matte(Color::gray());
+        Self {
+            point,
+            normal,
+            material,
+        }
+    }
+    pub fn with_material(point: Point3D, normal: Point3D, material: Material) -> Self {
         Self {
             point,
             normal,
@@ -34,11 +40,20 @@ impl Hittable for Plane {
             return false
         }
         hit_record.point = ray.at(t);
-        hit_record.normal = self.normal;
+        hit_record.set_face_normal(ray, self.normal);
+        // world units along two directions in the plane, so textures repeat across it
+        let (u_axis, v_axis) = self.normal.unit_vector().orthonormal_basis();
+        let offset = hit_record.point - self.point;
+        hit_record.uv = (offset.dot(&u_axis), offset.dot(&v_axis));
+        hit_record.dpdu = u_axis;
+        hit_record.dpdv = v_axis;
         hit_record.material = Some(&self.material);
         hit_record.t = t;
         hit_record.hit_anything = true;
 
         true
     }
+    fn bounding_box(&self) -> Option<Aabb> {
+        None
+    }
 }
@@ -1,4 +1,7 @@
-use crate::material::{Light, Material};
+use std::f64::consts::PI;
+
+use crate::aabb::Aabb;
+use crate::material::Material;
 use crate::point3d::Point3D;
 use crate::ray::Ray;
 
@@ -18,15 +21,6 @@ impl Sphere {
             material,
         }
     }
-    pub fn new_light(center: Point3D, intensity: f64) -> Self {
-        let l = Light::new(intensity);
-        let material = Material::Light(l);
-        Self {
-            center,
-            radius: 1.,
-            material,
-        }
-    }
 }
 impl Hittable for Sphere {
     fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
@@ -58,13 +52,28 @@ impl Hittable for Sphere {
         // front face tracking
         let point = ray.at(root);
         let normal = (point -self.center) / self.radius;
-        let front_face = ray.direction.dot(&normal) < 0.0;
 
-        hit_record.point = ray.at(root);
-        hit_record.normal = if front_face { normal } else { normal * -1. };
+        hit_record.point = point;
+        hit_record.set_face_normal(ray, normal);
+        // longitude around the y axis and latitude from the bottom pole
+        let theta = (-normal.y()).clamp(-1., 1.).acos();
+        let phi = (-normal.z()).atan2(normal.x()) + PI;
+        hit_record.uv = (phi / (2. * PI), theta / PI);
+        let local = point - self.center;
+        hit_record.dpdu = Point3D::new(local.z(), 0., -local.x()) * (2. * PI);
+        hit_record.dpdv = Point3D::new(
+            -theta.cos() * phi.cos(),
+            theta.sin(),
+            theta.cos() * phi.sin(),
+        ) * (PI * self.radius);
         hit_record.material = Some(&self.material);
         hit_record.t = root;
         hit_record.hit_anything = true;
         true
     }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let r = self.radius.abs();
+        let extent = Point3D::new(r, r, r);
+        Some(Aabb::new(self.center - extent, self.center + extent))
+    }
 }
@@ -0,0 +1,89 @@
+use crate::aabb::Aabb;
+use crate::matrix::Matrix4;
+use crate::point3d::Point3D;
+use crate::ray::Ray;
+
+use super::{Hittable, Intersection};
+
+// Places an object with a matrix. Rays are moved into the object's own
+// space, so the object itself never needs to know it was transformed.
+pub struct Transformed<H: Hittable> {
+    object: H,
+    to_world: Matrix4,
+    to_object: Matrix4,
+    // normals need the inverse transpose to stay perpendicular to scaled surfaces
+    normal_to_world: Matrix4,
+}
+
+impl<H: Hittable> Transformed<H> {
+    // None when the matrix can't be inverted, e.g. a scale of 0
+    pub fn new(object: H, to_world: Matrix4) -> Option<Self> {
+        let to_object = to_world.inverse()?;
+        Some(Self {
+            object,
+            to_world,
+            to_object,
+            normal_to_world: to_object.transpose(),
+        })
+    }
+}
+
+impl<H: Hittable> Hittable for Transformed<H> {
+    fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
+        // the direction isn't normalized, so `t` means the same in both spaces
+        let local_ray = Ray::new(
+            self.to_object.transform_point(&ray.origin),
+            self.to_object.transform_vector(&ray.direction),
+        );
+        if !self.object.hit(&local_ray, hit_record) {
+            return false;
+        }
+        hit_record.point = ray.at(hit_record.t);
+        // the normal already faces the ray, the transform keeps it that way
+        hit_record.normal = self
+            .normal_to_world
+            .transform_vector(&hit_record.normal)
+            .unit_vector();
+        hit_record.dpdu = self.to_world.transform_vector(&hit_record.dpdu);
+        hit_record.dpdv = self.to_world.transform_vector(&hit_record.dpdv);
+        true
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let bounds = self.object.bounding_box()?;
+        let corners = (0..8).map(|i| {
+            Point3D::new(
+                if i & 1 == 0 { bounds.min.x() } else { bounds.max.x() },
+                if i & 2 == 0 { bounds.min.y() } else { bounds.max.y() },
+                if i & 4 == 0 { bounds.min.z() } else { bounds.max.z() },
+            )
+        });
+        Some(corners.fold(Aabb::empty(), |b, corner| {
+            b.grow(&self.to_world.transform_point(&corner))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{color::Color, material::Material, objects::Sphere};
+
+    #[test]
+    fn normals_stay_perpendicular_under_non_uniform_scale() {
+        let sphere = Sphere::new(Point3D::new(0., 0., 0.), 1., Material::matte(Color::white()));
+        // ellipsoid x^2 / 4 + y^2 + z^2 = 1
+        let ellipsoid = Transformed::new(sphere, Matrix4::scaling(Point3D::new(2., 1., 1.))).unwrap();
+        let ray = Ray::new(Point3D::new(1., 0., 5.), Point3D::new(0., 0., -1.));
+        let mut hit = Intersection::new();
+        assert!(ellipsoid.hit(&ray, &mut hit));
+
+        let z = 0.75f64.sqrt();
+        assert!((hit.point - Point3D::new(1., 0., z)).length() < 1e-9);
+        // gradient of the implicit surface
+        let expected = Point3D::new(0.5, 0., 2. * z).unit_vector();
+        assert!((hit.normal - expected).length() < 1e-9, "{:?}", hit.normal);
+        // the tangents still lie in the surface
+        assert!(hit.normal.dot(&hit.dpdu.unit_vector()).abs() < 1e-9);
+        assert!(hit.normal.dot(&hit.dpdv.unit_vector()).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,114 @@
+use crate::aabb::Aabb;
+use crate::material::Material;
+use crate::point3d::Point3D;
+use crate::ray::Ray;
+
+use super::{Hittable, Intersection};
+
+pub struct Triangle {
+    pub vertices: [Point3D; 3],
+    // per-vertex normals for smooth shading
+    pub normals: Option<[Point3D; 3]>,
+    pub uvs: Option<[(f64, f64); 3]>,
+    pub material: Material,
+}
+
+impl Triangle {
+    pub fn new(a: Point3D, b: Point3D, c: Point3D, material: Material) -> Self {
+        Self {
+            vertices: [a, b, c],
+            normals: None,
+            uvs: None,
+            material,
+        }
+    }
+    pub fn with_attributes(
+        vertices: [Point3D; 3],
+        normals: Option<[Point3D; 3]>,
+        uvs: Option<[(f64, f64); 3]>,
+        material: Material,
+    ) -> Self {
+        Self {
+            vertices,
+            normals,
+            uvs,
+            material,
+        }
+    }
+}
+
+impl Hittable for Triangle {
+    // Möller–Trumbore, u and v are the barycentric weights of the second and third vertex
+    fn hit<'a>(&'a self, ray: &Ray, hit_record: &mut Intersection<'a>) -> bool {
+        const EPSILON: f64 = 1e-12;
+        let [a, b, c] = self.vertices;
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = ray.direction.cross(&edge2);
+        let det = edge1.dot(&p);
+        // ray parallel to the triangle
+        if det.abs() < EPSILON {
+            return false;
+        }
+        let inv_det = 1. / det;
+        let s = ray.origin - a;
+        let u = s.dot(&p) * inv_det;
+        if !(0. ..=1.).contains(&u) {
+            return false;
+        }
+        let q = s.cross(&edge1);
+        let v = ray.direction.dot(&q) * inv_det;
+        if v < 0. || u + v > 1. {
+            return false;
+        }
+        let t = edge2.dot(&q) * inv_det;
+        if t < hit_record.t_min || t > hit_record.t {
+            return false;
+        }
+
+        let w = 1. - u - v;
+        let geometric_normal = edge1.cross(&edge2).unit_vector();
+        let normal = match self.normals {
+            Some([n0, n1, n2]) => (n0 * w + n1 * u + n2 * v).unit_vector(),
+            None => geometric_normal,
+        };
+        // the winding order decides which side is the front
+        let front_face = ray.direction.dot(&geometric_normal) < 0.0;
+
+        hit_record.point = ray.at(t);
+        hit_record.front_face = front_face;
+        hit_record.normal = if front_face { normal } else { normal * -1. };
+        hit_record.uv = match self.uvs {
+            Some([t0, t1, t2]) => (
+                t0.0 * w + t1.0 * u + t2.0 * v,
+                t0.1 * w + t1.1 * u + t2.1 * v,
+            ),
+            None => (u, v),
+        };
+        // the edges expressed in uv steps, solved for the steps along u and v
+        (hit_record.dpdu, hit_record.dpdv) = match self.uvs {
+            Some([t0, t1, t2]) => {
+                let (du1, dv1) = (t1.0 - t0.0, t1.1 - t0.1);
+                let (du2, dv2) = (t2.0 - t0.0, t2.1 - t0.1);
+                let uv_det = du1 * dv2 - dv1 * du2;
+                if uv_det.abs() < EPSILON {
+                    (edge1, edge2)
+                } else {
+                    (
+                        (edge1 * dv2 - edge2 * dv1) / uv_det,
+                        (edge2 * du1 - edge1 * du2) / uv_det,
+                    )
+                }
+            }
+            None => (edge1, edge2),
+        };
+        hit_record.material = Some(&self.material);
+        hit_record.t = t;
+        hit_record.hit_anything = true;
+        true
+    }
+    fn bounding_box(&self) -> Option<Aabb> {
+        let [a, b, c] = self.vertices;
+        Some(Aabb::new(a, b).grow(&c))
+    }
+}
@@ -1,5 +1,7 @@
-use rand::prelude::*;
-use std::ops::{Add, Div, Mul, Sub};
+use std::{
+    f64::consts::PI,
+    ops::{Add, Div, Mul, Sub},
+};
 
 #[derive(Clone, Copy, Debug)]
 pub struct Point3D {
@@ -12,24 +14,43 @@ impl Point3D {
     pub fn new(x: f64, y: f64, z: f64) -> Self {
         Self { x, y, z }
     }
-    pub fn random(min: f64, max: f64) -> Self {
-        let mut rng = rand::thread_rng();
-        Point3D::new(
-            rng.gen_range(min..=max),
-            rng.gen_range(min..=max),
-            rng.gen_range(min..=max),
-        )
-    }
-    pub fn random_in_unit_sphere() -> Self {
-        loop {
-            let p = Self::random(-1.0, 1.0);
-            if p.length_squared() < 1.0 {
-                return p;
-            }
+    // The constructors below turn sampler values in range 0 - 1 into points.
+    // They keep neighbouring values close, so well spread samples stay well spread.
+    pub fn on_unit_sphere((u, v): (f64, f64)) -> Self {
+        let z = 1. - 2. * u;
+        let r = (1. - z * z).max(0.).sqrt();
+        let phi = 2. * PI * v;
+        Point3D::new(r * phi.cos(), r * phi.sin(), z)
+    }
+    // `radius_sample` is a uniform value in range 0 - 1, not the radius itself
+    pub fn in_unit_sphere(direction: (f64, f64), radius_sample: f64) -> Self {
+        // the cube root keeps the density uniform across the volume
+        Point3D::on_unit_sphere(direction) * radius_sample.cbrt()
+    }
+    // concentric mapping from the square to the disk, z is always 0
+    pub fn in_unit_disk((u, v): (f64, f64)) -> Self {
+        let x = 2. * u - 1.;
+        let y = 2. * v - 1.;
+        if x == 0. && y == 0. {
+            return Point3D::new(0., 0., 0.);
         }
-    }
-    pub fn random_unit_vector() -> Self {
-        Point3D::random_in_unit_sphere().unit_vector()
+        let (r, theta) = if x.abs() > y.abs() {
+            (x, PI / 4. * (y / x))
+        } else {
+            (y, PI / 2. - PI / 4. * (x / y))
+        };
+        Point3D::new(r * theta.cos(), r * theta.sin(), 0.)
+    }
+    // two unit vectors perpendicular to this unit vector and to each other
+    pub fn orthonormal_basis(&self) -> (Point3D, Point3D) {
+        let helper = if self.x.abs() > 0.9 {
+            Point3D::new(0., 1., 0.)
+        } else {
+            Point3D::new(1., 0., 0.)
+        };
+        let u = self.cross(&helper).unit_vector();
+        let v = self.cross(&u);
+        (u, v)
     }
     pub fn set_x(&mut self, x: f64) {
         self.x = x
@@ -49,11 +70,24 @@ impl Point3D {
     pub fn z(&self) -> f64 {
         self.z
     }
+    pub fn axis(&self, axis: usize) -> f64 {
+        match axis {
+            0 => self.x,
+            1 => self.y,
+            _ => self.z,
+        }
+    }
+    pub fn min(&self, other: &Point3D) -> Point3D {
+        Point3D::new(self.x.min(other.x), self.y.min(other.y), self.z.min(other.z))
+    }
+    pub fn max(&self, other: &Point3D) -> Point3D {
+        Point3D::new(self.x.max(other.x), self.y.max(other.y), self.z.max(other.z))
+    }
     pub fn unit_vector(&self) -> Self {
         let length = self.length();
         Point3D::new(self.x / length, self.y / length, self.z / length)
     }
-    fn length(&self) -> f64 {
+    pub fn length(&self) -> f64 {
         self.distance(&Point3D::new(0.0, 0.0, 0.0))
     }
     pub fn length_squared(&self) -> f64 {
@@ -0,0 +1,25 @@
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::cell::RefCell;
+
+// Every render thread owns its generator. The renderer reseeds it before each
+// pixel, so the random sequence doesn't depend on which thread renders the pixel.
+thread_local! {
+    static RNG: RefCell<StdRng> = RefCell::new(StdRng::seed_from_u64(0));
+}
+
+pub fn reseed(seed: u64) {
+    RNG.with(|rng| *rng.borrow_mut() = StdRng::seed_from_u64(seed));
+}
+
+// in range 0 -1
+pub fn random_float() -> f64 {
+    RNG.with(|rng| rng.borrow_mut().gen())
+}
+
+// splitmix64 finalizer, turns neighbouring indices into unrelated seeds
+pub fn stream_seed(seed: u64, index: u64) -> u64 {
+    let mut z = seed.wrapping_add(index.wrapping_add(1).wrapping_mul(0x9e3779b97f4a7c15));
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
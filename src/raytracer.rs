@@ -1,128 +1,153 @@
-use crate::{objects::Intersection, *};
-use rand::prelude::*;
+use crate::{
+    integrator::Integrator,
+    light::{LightSample, LightSource},
+    image::Film,
+    objects::Intersection,
+    random::*,
+    sampler::{SampleSource, Sampler},
+    config::Config,
+    *,
+};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+};
+
+const TILE_SIZE: usize = 16;
+pub const DEFAULT_SEED: u64 = 0;
+// sky color for rays that leave the scene
+pub const BACKGROUND: Color = Color::new(0.5, 0.7, 1.);
 
 pub struct RayTracer {
-    max_depth: i32,
+    integrator: Box<dyn Integrator>,
+    seed: u64,
+    threads: usize,
+    sampler: SampleSource,
 
     pub camera: Camera,
-    pub objects: Vec<Box<dyn Hittable>>,
+    pub scene: Bvh,
     pub image: Image,
-    pub light: Sphere,
+    pub lights: Vec<LightSource>,
+}
+
+// pixel range [x0, x1) x [y0, y1) of the image
+struct Tile {
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
 }
 
 impl RayTracer {
-    pub fn new(
-        image: Image,
-        camera: Camera,
-        objects: Vec<Box<dyn Hittable>>,
-        light: Sphere,
-    ) -> Self {
+    pub fn new(config: Config) -> Self {
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let samples = config.image.samples_per_pixel as usize;
         Self {
-            image,
-            camera,
-            objects,
-            light,
-            max_depth:50,
+            sampler: SampleSource::new(config.sampler, samples, config.seed),
+            image: config.image,
+            camera: config.camera,
+            scene: Bvh::new(config.objects),
+            lights: config.lights,
+            integrator: config.integrator.build(),
+            seed: config.seed,
+            threads,
         }
     }
+    // the image doesn't depend on the number of threads
+    pub fn set_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
 
     pub fn render(&mut self) {
-        for x in 0..self.image.width {
-            eprintln!("Line: {x} of {}", self.image.width);
-            for y in 0..self.image.height {
-                let pixel = self.intersect(x, y);
-                self.image.set_pixel(x, y, pixel);
+        let tiles = self.tiles();
+        let next_tile = AtomicUsize::new(0);
+        let finished = AtomicUsize::new(0);
+        let rendered = Mutex::new(Vec::with_capacity(tiles.len()));
+
+        let tracer = &*self;
+        thread::scope(|scope| {
+            for _ in 0..tracer.threads {
+                scope.spawn(|| loop {
+                    let index = next_tile.fetch_add(1, Ordering::Relaxed);
+                    let Some(tile) = tiles.get(index) else {
+                        break;
+                    };
+                    let film = tracer.render_tile(tile);
+                    rendered.lock().unwrap().push((index, film));
+                    let done = finished.fetch_add(1, Ordering::Relaxed) + 1;
+                    eprintln!("Tile: {done} of {}", tiles.len());
+                });
             }
+        });
+
+        // neighbouring tiles overlap, merging in a fixed order keeps the sums identical between runs
+        let mut rendered = rendered.into_inner().unwrap();
+        rendered.sort_by_key(|(index, _)| *index);
+        for (_, film) in &rendered {
+            self.image.merge(film);
         }
         eprintln!("Done");
-        self.image.save_image();
     }
-    fn intersect(&self, col: usize, row: usize) -> Color {
-        let mut pixel = Color::new(0., 0., 0.);
-        for _i in 0..self.image.samples_per_pixel {
-            let u = (2.0 * (col as f64 + random_float())) / self.image.width as f64 - 1.0;
-            let v = (-2.0 * (row as f64) + random_float()) / self.image.height as f64 + 1.0;
-            let ray = self.camera.make_ray(u, v);
-            let sample_pixel = self.find_pixel_color(&ray, self.max_depth);
-            pixel = pixel + sample_pixel;
+    fn tiles(&self) -> Vec<Tile> {
+        let mut tiles = vec![];
+        for y0 in (0..self.image.height).step_by(TILE_SIZE) {
+            for x0 in (0..self.image.width).step_by(TILE_SIZE) {
+                tiles.push(Tile {
+                    x0,
+                    y0,
+                    x1: (x0 + TILE_SIZE).min(self.image.width),
+                    y1: (y0 + TILE_SIZE).min(self.image.height),
+                });
+            }
         }
-        pixel
+        tiles
     }
-
-    fn find_pixel_color(&self, ray: &ray::Ray, depth: i32) -> Color {
-        if depth <= 0 {
-            return Color::black();
+    fn render_tile(&self, tile: &Tile) -> Film {
+        let mut film = self.image.film(tile.x0, tile.y0, tile.x1, tile.y1);
+        let mut sampler = self.sampler.clone();
+        for y in tile.y0..tile.y1 {
+            for x in tile.x0..tile.x1 {
+                self.intersect(&mut film, &mut sampler, x, y);
+            }
         }
-        let intersection = self.hit_scene(&ray);
-
-        if intersection.hit_anything {
-
-            let bounce = match intersection.material.unwrap() {
-                Material::Matte(m) => m.scatter(&ray, &intersection),
-                Material::Metal(m) => m.scatter(&ray, &intersection),
-                Material::Light(l) => l.scatter(&ray, &intersection),
+        film
+    }
+    fn intersect(&self, film: &mut Film, sampler: &mut dyn Sampler, col: usize, row: usize) {
+        // every pixel has its own random stream, so it doesn't matter which thread renders it
+        reseed(stream_seed(self.seed, (col + row * self.image.width) as u64));
+        for i in 0..self.image.samples_per_pixel {
+            sampler.start_pixel_sample(col, row, i as usize);
+            let (dx, dy) = sampler.next_2d();
+            let x = col as f64 + dx;
+            let y = row as f64 + dy;
+            let u = 2.0 * x / self.image.width as f64 - 1.0;
+            let v = 1.0 - 2.0 * y / self.image.height as f64;
+            // pixels outside of the projection stay black
+            let sample_pixel = match self.camera.make_ray(u, v, sampler) {
+                Some(ray) => self.integrator.radiance(self, ray, sampler),
+                None => Color::black(),
             };
-
-            if bounce.is_none() {
-                return Color::black();
-            }
-
-            let (bounce_ray, color) = bounce.unwrap();
-            let target_color = self.find_pixel_color(&bounce_ray, depth - 1);
-            let pixel_color = color * target_color * 0.5;
-            // Check if light source direct
-            let light_blocked = self.cast_ray_to_light(&intersection.point);
-            if light_blocked.is_some() {
-                return pixel_color;
-            }
-            return self.light_specular_diffuse_adjustment(&intersection) + pixel_color;
-        } else {
-            // return Color::white();
-            Color::new(0.5, 0.7, 1.) 
+            film.add_sample(x, y, sample_pixel);
         }
     }
-    fn hit_scene(&self, ray: &ray::Ray) -> Intersection {
+
+    pub(crate) fn hit_scene(&self, ray: &ray::Ray) -> Intersection<'_> {
         let mut hit_record = Intersection::new();
-        self.objects.iter().for_each(|object| {
-            object.hit(&ray, &mut hit_record);
-            });
+        self.scene.hit(ray, &mut hit_record);
         hit_record
     }
-    fn cast_ray_to_light(&self, intersection_point: &Point3D) -> Option<objects::Intersection> {
-        let light_vec = (self.light.center - *intersection_point).unit_vector();
-        let shadow_ray = ray::Ray::new(intersection_point.clone(), light_vec);
-        let result = self.hit_scene(&shadow_ray);
-        if result.hit_anything{
-            return Some(result)
-        }
-        None
+    // true when something blocks the way between the point and the light
+    pub(crate) fn cast_ray_to_light(&self, intersection_point: &Point3D, light: &LightSample) -> bool {
+        self.occluded(intersection_point, &light.direction, light.distance)
     }
-    fn light_specular_diffuse_adjustment(&self, intersection: &objects::Intersection) -> Color {
-        const DIFFUSE_COEF: f64 = 0.4;
-        const SPECULAR_COEF: f64 = 0.2;
-
-        let light_vec = (self.light.center - intersection.point).unit_vector();
-        let light_angle = light_vec.dot(&intersection.normal);
-        let reflection_vec =
-            light_vec * -2. * light_vec.dot(&intersection.normal) * intersection.normal;
-        let view_direction = (intersection.point - self.camera.origin).unit_vector();
-        let reflection_angle = reflection_vec.dot(&view_direction);
-
-        let diffuse_reflection = if light_angle < 0. { 0. } else { light_angle };
-        let specular = if reflection_angle < 0. {
-            0.
-        } else {
-            reflection_angle
-        };
-        let specular_reflection = specular.powf(1.);
-
-        (Color::white() * DIFFUSE_COEF * diffuse_reflection
-            + Color::white() * SPECULAR_COEF * specular_reflection)
-            * self.light.material.intensity()
+    // true when something is closer than `distance` along the direction
+    pub(crate) fn occluded(&self, origin: &Point3D, direction: &Point3D, distance: f64) -> bool {
+        let shadow_ray = ray::Ray::new(*origin, *direction);
+        let mut hit_record = Intersection::new();
+        hit_record.t = distance;
+        self.scene.hit(&shadow_ray, &mut hit_record)
     }
 }
-
-// in range 0 -1
-fn random_float() -> f64 {
-    rand::thread_rng().gen()
-}
@@ -0,0 +1,336 @@
+use crate::random::{random_float, stream_seed};
+
+// Source of the sample values for one pixel sample. Every call hands out the
+// next dimension, so the camera, lights and materials each get their own
+// well distributed values as long as they ask in the same order.
+pub trait Sampler {
+    // start sample `index` of pixel (x, y), back at the first dimension
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize);
+    // in range 0 - 1
+    fn next_1d(&mut self) -> f64;
+    fn next_2d(&mut self) -> (f64, f64);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SamplerKind {
+    Independent,
+    Stratified,
+    Halton,
+    Sobol,
+}
+
+impl SamplerKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "independent" => Some(SamplerKind::Independent),
+            "stratified" => Some(SamplerKind::Stratified),
+            "halton" => Some(SamplerKind::Halton),
+            "sobol" => Some(SamplerKind::Sobol),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum SampleSource {
+    Independent(IndependentSampler),
+    Stratified(StratifiedSampler),
+    Halton(HaltonSampler),
+    Sobol(SobolSampler),
+}
+
+impl SampleSource {
+    pub fn new(kind: SamplerKind, samples_per_pixel: usize, seed: u64) -> Self {
+        match kind {
+            SamplerKind::Independent => SampleSource::Independent(IndependentSampler),
+            SamplerKind::Stratified => {
+                SampleSource::Stratified(StratifiedSampler::new(samples_per_pixel, seed))
+            }
+            SamplerKind::Halton => SampleSource::Halton(HaltonSampler::new(seed)),
+            SamplerKind::Sobol => SampleSource::Sobol(SobolSampler::new(seed)),
+        }
+    }
+}
+
+impl Sampler for SampleSource {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        match self {
+            SampleSource::Independent(s) => s.start_pixel_sample(x, y, index),
+            SampleSource::Stratified(s) => s.start_pixel_sample(x, y, index),
+            SampleSource::Halton(s) => s.start_pixel_sample(x, y, index),
+            SampleSource::Sobol(s) => s.start_pixel_sample(x, y, index),
+        }
+    }
+    fn next_1d(&mut self) -> f64 {
+        match self {
+            SampleSource::Independent(s) => s.next_1d(),
+            SampleSource::Stratified(s) => s.next_1d(),
+            SampleSource::Halton(s) => s.next_1d(),
+            SampleSource::Sobol(s) => s.next_1d(),
+        }
+    }
+    fn next_2d(&mut self) -> (f64, f64) {
+        match self {
+            SampleSource::Independent(s) => s.next_2d(),
+            SampleSource::Stratified(s) => s.next_2d(),
+            SampleSource::Halton(s) => s.next_2d(),
+            SampleSource::Sobol(s) => s.next_2d(),
+        }
+    }
+}
+
+// Where a sampler is in the sequence: which pixel, which sample and which dimension
+#[derive(Clone, Default)]
+struct Position {
+    pixel_seed: u64,
+    index: usize,
+    dimension: u64,
+}
+
+impl Position {
+    fn start(&mut self, seed: u64, x: usize, y: usize, index: usize) {
+        self.pixel_seed = stream_seed(stream_seed(seed, x as u64), y as u64);
+        self.index = index;
+        self.dimension = 0;
+    }
+    // hash of the pixel and the current dimension, then moves on to the next dimension
+    fn advance(&mut self) -> u64 {
+        let hash = stream_seed(self.pixel_seed, self.dimension);
+        self.dimension += 1;
+        hash
+    }
+}
+
+// Plain uniform random numbers, every sample is independent of the others
+#[derive(Clone)]
+pub struct IndependentSampler;
+
+impl Sampler for IndependentSampler {
+    fn start_pixel_sample(&mut self, _x: usize, _y: usize, _index: usize) {}
+    fn next_1d(&mut self) -> f64 {
+        random_float()
+    }
+    fn next_2d(&mut self) -> (f64, f64) {
+        (random_float(), random_float())
+    }
+}
+
+// Splits every dimension into one cell per sample and jitters inside the cells.
+// The cells are visited in a different random order for every pixel and dimension.
+#[derive(Clone)]
+pub struct StratifiedSampler {
+    samples: usize,
+    // 2D cells form a grid of columns x rows == samples
+    columns: usize,
+    rows: usize,
+    seed: u64,
+    position: Position,
+}
+
+impl StratifiedSampler {
+    pub fn new(samples: usize, seed: u64) -> Self {
+        let samples = samples.max(1);
+        // the most square grid that holds exactly `samples` cells
+        let columns = (1..=samples)
+            .take_while(|c| c * c <= samples)
+            .filter(|c| samples.is_multiple_of(*c))
+            .last()
+            .unwrap_or(1);
+        Self {
+            samples,
+            columns,
+            rows: samples / columns,
+            seed,
+            position: Position::default(),
+        }
+    }
+    fn cell(&mut self) -> usize {
+        let hash = self.position.advance();
+        permutation_element(self.position.index % self.samples, self.samples, hash as u32)
+    }
+}
+
+impl Sampler for StratifiedSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.position.start(self.seed, x, y, index);
+    }
+    fn next_1d(&mut self) -> f64 {
+        let cell = self.cell();
+        (cell as f64 + random_float()) / self.samples as f64
+    }
+    fn next_2d(&mut self) -> (f64, f64) {
+        let cell = self.cell();
+        let column = cell % self.columns;
+        let row = cell / self.columns;
+        (
+            (column as f64 + random_float()) / self.columns as f64,
+            (row as f64 + random_float()) / self.rows as f64,
+        )
+    }
+}
+
+// Halton sequence, one prime base per dimension. Every pixel and dimension
+// gets a random toroidal shift so neighbouring pixels don't share patterns.
+#[derive(Clone)]
+pub struct HaltonSampler {
+    seed: u64,
+    position: Position,
+}
+
+const PRIMES: [u64; 32] = [
+    2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71, 73, 79, 83, 89,
+    97, 101, 103, 107, 109, 113, 127, 131,
+];
+
+impl HaltonSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: Position::default(),
+        }
+    }
+}
+
+impl Sampler for HaltonSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.position.start(self.seed, x, y, index);
+    }
+    fn next_1d(&mut self) -> f64 {
+        let dimension = self.position.dimension as usize;
+        let hash = self.position.advance();
+        // large bases need many samples to fill the range, so deep dimensions fall back to random numbers
+        let Some(&base) = PRIMES.get(dimension) else {
+            return random_float();
+        };
+        let shift = unit_float(hash as u32);
+        let value = radical_inverse(base, self.position.index as u64) + shift;
+        value - value.floor()
+    }
+    fn next_2d(&mut self) -> (f64, f64) {
+        (self.next_1d(), self.next_1d())
+    }
+}
+
+// digits of `index` in `base` mirrored around the decimal point
+fn radical_inverse(base: u64, mut index: u64) -> f64 {
+    let inverse_base = 1. / base as f64;
+    let mut factor = inverse_base;
+    let mut value = 0.;
+    while index > 0 {
+        value += (index % base) as f64 * factor;
+        index /= base;
+        factor *= inverse_base;
+    }
+    value
+}
+
+// First two dimensions of the Sobol sequence, reused for every pair of
+// dimensions with Owen scrambling. The sample order is shuffled separately
+// for every pair, so the pairs don't correlate with each other.
+#[derive(Clone)]
+pub struct SobolSampler {
+    seed: u64,
+    position: Position,
+}
+
+impl SobolSampler {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            position: Position::default(),
+        }
+    }
+    fn shuffled_index(&self, hash: u64) -> u32 {
+        nested_uniform_scramble(self.position.index as u32, hash as u32)
+    }
+}
+
+impl Sampler for SobolSampler {
+    fn start_pixel_sample(&mut self, x: usize, y: usize, index: usize) {
+        self.position.start(self.seed, x, y, index);
+    }
+    fn next_1d(&mut self) -> f64 {
+        let hash = self.position.advance();
+        let index = self.shuffled_index(hash);
+        unit_float(nested_uniform_scramble(index.reverse_bits(), (hash >> 32) as u32))
+    }
+    fn next_2d(&mut self) -> (f64, f64) {
+        let hash = self.position.advance();
+        let index = self.shuffled_index(hash);
+        let scramble = stream_seed(hash, 0);
+        (
+            unit_float(nested_uniform_scramble(index.reverse_bits(), scramble as u32)),
+            unit_float(nested_uniform_scramble(sobol_second(index), (scramble >> 32) as u32)),
+        )
+    }
+}
+
+// second dimension of the Sobol sequence, its direction numbers follow v ^= v >> 1
+fn sobol_second(mut index: u32) -> u32 {
+    let mut value = 0;
+    let mut direction = 1 << 31;
+    while index != 0 {
+        if index & 1 != 0 {
+            value ^= direction;
+        }
+        index >>= 1;
+        direction ^= direction >> 1;
+    }
+    value
+}
+
+// Laine–Karras hash, only ever changes a bit based on the bits below it
+fn laine_karras_permutation(mut x: u32, seed: u32) -> u32 {
+    x = x.wrapping_add(seed);
+    x ^= x.wrapping_mul(0x6c50b47c);
+    x ^= x.wrapping_mul(0xb82f1e52);
+    x ^= x.wrapping_mul(0xc7afe638);
+    x ^= x.wrapping_mul(0x8d22f6e6);
+    x
+}
+
+// Owen scrambling of a 32 bit fixed point number
+fn nested_uniform_scramble(x: u32, seed: u32) -> u32 {
+    laine_karras_permutation(x.reverse_bits(), seed).reverse_bits()
+}
+
+// Kensler's hashed permutation: element `index` of a random permutation of 0..count
+fn permutation_element(index: usize, count: usize, seed: u32) -> usize {
+    let count = count as u32;
+    let mut w = count - 1;
+    w |= w >> 1;
+    w |= w >> 2;
+    w |= w >> 4;
+    w |= w >> 8;
+    w |= w >> 16;
+    let mut i = index as u32;
+    loop {
+        i ^= seed;
+        i = i.wrapping_mul(0xe170893d);
+        i ^= seed >> 16;
+        i ^= (i & w) >> 4;
+        i ^= seed >> 8;
+        i = i.wrapping_mul(0x0929eb3f);
+        i ^= seed >> 23;
+        i ^= (i & w) >> 1;
+        i = i.wrapping_mul(1 | seed >> 27);
+        i = i.wrapping_mul(0x6935fa69);
+        i ^= (i & w) >> 11;
+        i = i.wrapping_mul(0x74dcb303);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0x9e501cc3);
+        i ^= (i & w) >> 2;
+        i = i.wrapping_mul(0xc860a3df);
+        i &= w;
+        i ^= i >> 5;
+        if i < count {
+            break;
+        }
+    }
+    (i.wrapping_add(seed) % count) as usize
+}
+
+// 32 bit fixed point to a float in range 0 - 1, never exactly 1
+fn unit_float(x: u32) -> f64 {
+    x as f64 / 4294967296.
+}
@@ -0,0 +1,1307 @@
+use crate::{
+    bump::Bump,
+    camera::{Camera, FieldOfView, Focus, Lens, Projection},
+    color::Color,
+    config::Config,
+    filter::{Filter, FilterKind},
+    image::Image,
+    integrator::{IntegratorKind, DEFAULT_AO_DISTANCE},
+    raytracer::DEFAULT_SEED,
+    sampler::SamplerKind,
+    tonemap::{Operator, ToneMap},
+    light::{DirectionalLight, LightSource, PointLight, SphereLight, SpotLight},
+    material::{Material, Matte, Metal},
+    matrix::Matrix4,
+    objects::{Cone, Cube, Cylinder, Hittable, Mesh, Plane, Sphere, Transformed, Triangle},
+    point3d::Point3D,
+    texture::{
+        Checker, Gradient, GradientDirection, ImageTexture, Marble, Noise, NoiseSettings,
+        SolidColor, Texture, Turbulence, Wood,
+    },
+};
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+// Scene description format
+//
+// One directive per line, followed by `key=value` properties:
+//
+//     # comment
+//     image width=400 height=300 samples=25
+//     camera origin=(0, 2, 0) target=(0, 0.5, -5) up=(0, 1, 0) fov=70
+//     sphere_light center=(8, 12, -7) radius=1 intensity=100
+//     sphere center=(0, 0.2, -7.5) radius=1.2 material=matte color=green
+//     mesh file="models/sphere.obj" material=metal
+//
+// Values are numbers, vectors `(x, y, z)`, bare identifiers or "strings".
+// File paths are relative to the directory of the scene file.
+
+#[derive(Debug)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+pub fn parse(source: &str, base_dir: &Path) -> Result<Config, ParseError> {
+    let mut builder = SceneBuilder::new(base_dir);
+    let mut line_count = 0;
+    for (index, line) in source.lines().enumerate() {
+        line_count = index + 1;
+        let tokens = tokenize(line, index + 1)?;
+        if tokens.is_empty() {
+            continue;
+        }
+        let mut directive = Directive::parse(&tokens, index + 1)?;
+        builder.add(&mut directive)?;
+        directive.finish()?;
+    }
+    builder.build(line_count + 1)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Str(String),
+    Equals,
+    LParen,
+    RParen,
+    Comma,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Ident(s) => write!(f, "`{s}`"),
+            Token::Number(n) => write!(f, "`{n}`"),
+            Token::Str(s) => write!(f, "\"{s}\""),
+            Token::Equals => write!(f, "`=`"),
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::Comma => write!(f, "`,`"),
+        }
+    }
+}
+
+struct Spanned {
+    token: Token,
+    column: usize,
+}
+
+fn tokenize(line: &str, line_no: usize) -> Result<Vec<Spanned>, ParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        if c == '#' {
+            break;
+        }
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = match c {
+            '=' => Token::Equals,
+            '(' => Token::LParen,
+            ')' => Token::RParen,
+            ',' => Token::Comma,
+            '"' => {
+                let start = i + 1;
+                let end = (start..chars.len())
+                    .find(|&j| chars[j] == '"')
+                    .ok_or_else(|| ParseError::new(line_no, column, "unterminated string"))?;
+                i = end;
+                Token::Str(chars[start..end].iter().collect())
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => {
+                let start = i;
+                while i + 1 < chars.len() {
+                    let next = chars[i + 1];
+                    let exponent_sign =
+                        (next == '-' || next == '+') && matches!(chars[i], 'e' | 'E');
+                    if next.is_ascii_digit() || next == '.' || next == 'e' || next == 'E' || exponent_sign
+                    {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let text: String = chars[start..=i].iter().collect();
+                let number = text.parse::<f64>().map_err(|_| {
+                    ParseError::new(line_no, column, format!("invalid number `{text}`"))
+                })?;
+                Token::Number(number)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i + 1 < chars.len() && (chars[i + 1].is_alphanumeric() || chars[i + 1] == '_') {
+                    i += 1;
+                }
+                Token::Ident(chars[start..=i].iter().collect())
+            }
+            c => {
+                return Err(ParseError::new(
+                    line_no,
+                    column,
+                    format!("unexpected character `{c}`"),
+                ))
+            }
+        };
+        tokens.push(Spanned { token, column });
+        i += 1;
+    }
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone)]
+enum Value {
+    Number(f64),
+    Vector(Point3D),
+    Ident(String),
+    Str(String),
+}
+
+impl Value {
+    fn describe(&self) -> &'static str {
+        match self {
+            Value::Number(_) => "a number",
+            Value::Vector(_) => "a vector",
+            Value::Ident(_) => "a name",
+            Value::Str(_) => "a string",
+        }
+    }
+}
+
+struct Property {
+    value: Value,
+    column: usize,
+    used: bool,
+}
+
+struct Directive {
+    name: String,
+    line: usize,
+    column: usize,
+    properties: HashMap<String, Property>,
+}
+
+impl Directive {
+    fn parse(tokens: &[Spanned], line: usize) -> Result<Self, ParseError> {
+        let mut cursor = Cursor { tokens, pos: 0, line };
+        let (name, column) = cursor.ident()?;
+        let mut directive = Directive {
+            name,
+            line,
+            column,
+            properties: HashMap::new(),
+        };
+        while !cursor.done() {
+            let (key, key_column) = cursor.ident()?;
+            cursor.expect(Token::Equals)?;
+            let value = cursor.value()?;
+            if directive.properties.contains_key(&key) {
+                return Err(ParseError::new(
+                    line,
+                    key_column,
+                    format!("duplicate property `{key}`"),
+                ));
+            }
+            let property = Property {
+                value,
+                column: key_column,
+                used: false,
+            };
+            directive.properties.insert(key, property);
+        }
+        Ok(directive)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    fn take(&mut self, key: &str) -> Option<(Value, usize)> {
+        let property = self.properties.get_mut(key)?;
+        property.used = true;
+        Some((property.value.clone(), property.column))
+    }
+
+    fn mismatch(&self, key: &str, column: usize, expected: &str, found: &Value) -> ParseError {
+        ParseError::new(
+            self.line,
+            column,
+            format!("`{key}` must be {expected}, found {}", found.describe()),
+        )
+    }
+
+    fn optional_number(&mut self, key: &str) -> Result<Option<f64>, ParseError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Number(n), _)) => Ok(Some(n)),
+            Some((other, column)) => Err(self.mismatch(key, column, "a number", &other)),
+        }
+    }
+
+    fn number(&mut self, key: &str) -> Result<f64, ParseError> {
+        self.optional_number(key)?
+            .ok_or_else(|| self.error(format!("`{}` requires `{key}`", self.name)))
+    }
+
+    fn optional_positive(&mut self, key: &str) -> Result<Option<f64>, ParseError> {
+        let column = self.properties.get(key).map(|p| p.column).unwrap_or(self.column);
+        match self.optional_number(key)? {
+            Some(n) if n <= 0. => Err(ParseError::new(
+                self.line,
+                column,
+                format!("`{key}` must be positive"),
+            )),
+            n => Ok(n),
+        }
+    }
+
+    fn positive(&mut self, key: &str) -> Result<f64, ParseError> {
+        self.optional_positive(key)?
+            .ok_or_else(|| self.error(format!("`{}` requires `{key}`", self.name)))
+    }
+
+    fn optional_non_negative(&mut self, key: &str) -> Result<Option<f64>, ParseError> {
+        let column = self.properties.get(key).map(|p| p.column).unwrap_or(self.column);
+        match self.optional_number(key)? {
+            Some(n) if n < 0. => Err(ParseError::new(
+                self.line,
+                column,
+                format!("`{key}` must not be negative"),
+            )),
+            n => Ok(n),
+        }
+    }
+
+    fn non_negative(&mut self, key: &str) -> Result<f64, ParseError> {
+        self.optional_non_negative(key)?
+            .ok_or_else(|| self.error(format!("`{}` requires `{key}`", self.name)))
+    }
+
+    fn optional_count(&mut self, key: &str) -> Result<Option<usize>, ParseError> {
+        let column = self.properties.get(key).map(|p| p.column).unwrap_or(self.column);
+        match self.optional_number(key)? {
+            None => Ok(None),
+            Some(n) if n >= 1. && n.fract() == 0. => Ok(Some(n as usize)),
+            Some(_) => Err(ParseError::new(
+                self.line,
+                column,
+                format!("`{key}` must be a positive whole number"),
+            )),
+        }
+    }
+
+    // exactly one of `fov`, `horizontal_fov` or `focal_length`, angles in degrees below `max_angle`
+    fn field_of_view(&mut self, max_angle: f64) -> Result<FieldOfView, ParseError> {
+        let keys = ["fov", "horizontal_fov", "focal_length"];
+        let given: Vec<&str> = keys
+            .into_iter()
+            .filter(|key| self.properties.contains_key(*key))
+            .collect();
+        if given.len() != 1 {
+            return Err(self.error("camera needs exactly one of `fov`, `horizontal_fov` or `focal_length`"));
+        }
+        let key = given[0];
+        let column = self.properties[key].column;
+        let value = self.number(key)?;
+        if key == "focal_length" {
+            // full frame 35mm sensor unless told otherwise
+            let sensor_width = self.optional_number("sensor_width")?.unwrap_or(36.);
+            let sensor_height = self.optional_number("sensor_height")?.unwrap_or(24.);
+            if value <= 0. || sensor_width <= 0. || sensor_height <= 0. {
+                return Err(ParseError::new(self.line, column, "lens and sensor sizes must be positive"));
+            }
+            return Ok(FieldOfView::Physical {
+                focal_length: value,
+                sensor_width,
+                sensor_height,
+            });
+        }
+        if value <= 0. || value >= max_angle {
+            return Err(ParseError::new(
+                self.line,
+                column,
+                format!("`{key}` must be between 0 and {max_angle} degrees"),
+            ));
+        }
+        if key == "fov" {
+            Ok(FieldOfView::Vertical(value.to_radians()))
+        } else {
+            Ok(FieldOfView::Horizontal(value.to_radians()))
+        }
+    }
+
+    // a distance or `auto`
+    fn optional_focus(&mut self, key: &str) -> Result<Option<Focus>, ParseError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Number(n), _)) => Ok(Some(Focus::Distance(n))),
+            Some((Value::Ident(name), _)) if name == "auto" => Ok(Some(Focus::Auto)),
+            Some((other, column)) => {
+                Err(self.mismatch(key, column, "a number or `auto`", &other))
+            }
+        }
+    }
+
+    fn point(&mut self, key: &str) -> Result<Point3D, ParseError> {
+        match self.take(key) {
+            None => Err(self.error(format!("`{}` requires `{key}`", self.name))),
+            Some((Value::Vector(p), _)) => Ok(p),
+            Some((other, column)) => Err(self.mismatch(key, column, "a vector", &other)),
+        }
+    }
+
+    fn optional_point(&mut self, key: &str) -> Result<Option<Point3D>, ParseError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Vector(p), _)) => Ok(Some(p)),
+            Some((other, column)) => Err(self.mismatch(key, column, "a vector", &other)),
+        }
+    }
+
+    // unit vector, a zero vector has no direction
+    fn direction(&mut self, key: &str) -> Result<Point3D, ParseError> {
+        let column = self.properties.get(key).map(|p| p.column).unwrap_or(self.column);
+        let direction = self.point(key)?;
+        if direction.length_squared() == 0. {
+            return Err(ParseError::new(self.line, column, format!("`{key}` must not be 0")));
+        }
+        Ok(direction.unit_vector())
+    }
+
+    // the other end of an axis starting at `base`, given as `top` or as an upright `height`
+    fn top(&mut self, base: Point3D) -> Result<Point3D, ParseError> {
+        let top = match (self.optional_point("top")?, self.optional_number("height")?) {
+            (Some(top), None) => top,
+            (None, Some(height)) => base + Point3D::new(0., height, 0.),
+            _ => return Err(self.error(format!("`{}` needs either `top` or `height`", self.name))),
+        };
+        if (top - base).length_squared() == 0. {
+            return Err(self.error("`top` must be different from `base`"));
+        }
+        Ok(top)
+    }
+
+    // `scale` is a number or a vector, `rotate` holds angles in degrees, None when none are given
+    fn optional_transform(&mut self) -> Result<Option<Matrix4>, ParseError> {
+        let scale = match self.take("scale") {
+            None => None,
+            Some((Value::Number(n), _)) => Some(Point3D::new(n, n, n)),
+            Some((Value::Vector(p), _)) => Some(p),
+            Some((other, column)) => {
+                return Err(self.mismatch("scale", column, "a number or a vector", &other))
+            }
+        };
+        let rotate = self.optional_point("rotate")?;
+        let translate = self.optional_point("translate")?;
+        if scale.is_none() && rotate.is_none() && translate.is_none() {
+            return Ok(None);
+        }
+        let mut transform = Matrix4::scaling(scale.unwrap_or(Point3D::new(1., 1., 1.)));
+        if let Some(angles) = rotate {
+            transform = Matrix4::rotation_z(angles.z().to_radians())
+                * Matrix4::rotation_y(angles.y().to_radians())
+                * Matrix4::rotation_x(angles.x().to_radians())
+                * transform;
+        }
+        if let Some(offset) = translate {
+            transform = Matrix4::translation(offset) * transform;
+        }
+        Ok(Some(transform))
+    }
+
+    fn string(&mut self, key: &str) -> Result<(String, usize), ParseError> {
+        match self.take(key) {
+            None => Err(self.error(format!("`{}` requires `{key}`", self.name))),
+            Some((Value::Str(s), column)) => Ok((s, column)),
+            Some((other, column)) => Err(self.mismatch(key, column, "a string", &other)),
+        }
+    }
+
+    // whole number that fits exactly into a float, zero included
+    fn optional_seed(&mut self, key: &str) -> Result<Option<u64>, ParseError> {
+        let column = self.properties.get(key).map(|p| p.column).unwrap_or(self.column);
+        match self.optional_number(key)? {
+            None => Ok(None),
+            Some(n) if (0. ..=9007199254740992.).contains(&n) && n.fract() == 0. => Ok(Some(n as u64)),
+            Some(_) => Err(ParseError::new(
+                self.line,
+                column,
+                format!("`{key}` must be a whole number, 0 or more"),
+            )),
+        }
+    }
+
+    fn optional_ident(&mut self, key: &str) -> Result<Option<(String, usize)>, ParseError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Ident(s), column)) => Ok(Some((s, column))),
+            Some((other, column)) => Err(self.mismatch(key, column, "a name", &other)),
+        }
+    }
+
+    fn color(&mut self, key: &str) -> Result<Color, ParseError> {
+        self.optional_color(key)?
+            .ok_or_else(|| self.error(format!("`{}` requires `{key}`", self.name)))
+    }
+
+    fn optional_color(&mut self, key: &str) -> Result<Option<Color>, ParseError> {
+        match self.take(key) {
+            None => Ok(None),
+            Some((Value::Vector(p), _)) => Ok(Some(Color::new(p.x(), p.y(), p.z()))),
+            Some((Value::Ident(name), column)) => match name.as_str() {
+                "red" => Ok(Some(Color::red())),
+                "green" => Ok(Some(Color::green())),
+                "gray" => Ok(Some(Color::gray())),
+                "white" => Ok(Some(Color::white())),
+                "black" => Ok(Some(Color::black())),
+                _ => Err(ParseError::new(
+                    self.line,
+                    column,
+                    format!("unknown color `{name}`"),
+                )),
+            },
+            Some((other, column)) => Err(self.mismatch(key, column, "a color", &other)),
+        }
+    }
+
+    // image textures are looked up relative to `base_dir`
+    fn optional_material(&mut self, base_dir: &Path) -> Result<Option<Material>, ParseError> {
+        let (kind, column) = match self.optional_ident("material")? {
+            None => return Ok(None),
+            Some(kind) => kind,
+        };
+        let material = match kind.as_str() {
+            "matte" => {
+                let texture = match self.optional_texture(base_dir)? {
+                    Some(texture) => texture,
+                    None => Arc::new(SolidColor::new(self.color("color")?)),
+                };
+                Material::Matte(Matte::new(texture))
+            }
+            "metal" => {
+                let texture = match self.optional_texture(base_dir)? {
+                    Some(texture) => texture,
+                    None => {
+                        let color = self.optional_color("color")?.unwrap_or(Color::white());
+                        Arc::new(SolidColor::new(color))
+                    }
+                };
+                let roughness_column = self.properties.get("roughness").map(|p| p.column);
+                let roughness = self.optional_number("roughness")?.unwrap_or(0.);
+                if let Some(column) = roughness_column.filter(|_| !(0. ..=1.).contains(&roughness)) {
+                    return Err(ParseError::new(
+                        self.line,
+                        column,
+                        "`roughness` must be between 0 and 1",
+                    ));
+                }
+                Material::Metal(Metal::new(texture, roughness))
+            }
+            "light" => Material::light(self.number("intensity")?),
+            "dielectric" => Material::dielectric(self.optional_positive("ior")?.unwrap_or(1.5)),
+            _ => {
+                return Err(ParseError::new(
+                    self.line,
+                    column,
+                    format!("unknown material `{kind}`"),
+                ))
+            }
+        };
+        // lights have no surface detail, a bump on them is reported as unused
+        if let Material::Light(_) = material {
+            return Ok(Some(material));
+        }
+        match self.optional_bump(base_dir)? {
+            Some(bump) => Ok(Some(material.with_bump(bump))),
+            None => Ok(Some(material)),
+        }
+    }
+
+    // `normal_map="file.png"`, or `bump_map` with a height picture or `noise` / `turbulence`
+    fn optional_bump(&mut self, base_dir: &Path) -> Result<Option<Bump>, ParseError> {
+        match (self.take("normal_map"), self.take("bump_map")) {
+            (None, None) => Ok(None),
+            (Some(_), Some((_, column))) => Err(ParseError::new(
+                self.line,
+                column,
+                "use either `normal_map` or `bump_map`, not both",
+            )),
+            (Some((Value::Str(file), column)), None) => {
+                Ok(Some(Bump::NormalMap(self.data_texture(&file, column, base_dir)?)))
+            }
+            (Some((other, column)), None) => {
+                Err(self.mismatch("normal_map", column, "a file name", &other))
+            }
+            (None, Some((value, column))) => {
+                let texture: Arc<dyn Texture> = match value {
+                    Value::Str(file) => self.data_texture(&file, column, base_dir)?,
+                    Value::Ident(kind) => {
+                        let scale = self.optional_positive("bump_scale")?.unwrap_or(1.);
+                        let settings = NoiseSettings {
+                            scale,
+                            ..NoiseSettings::default()
+                        };
+                        let (low, high) = (Color::black(), Color::white());
+                        match kind.as_str() {
+                            "noise" => Arc::new(Noise::new(low, high, settings)),
+                            "turbulence" => Arc::new(Turbulence::new(low, high, settings)),
+                            _ => {
+                                return Err(ParseError::new(
+                                    self.line,
+                                    column,
+                                    format!("unknown bump map `{kind}`, use `noise`, `turbulence` or a file name"),
+                                ))
+                            }
+                        }
+                    }
+                    other => return Err(self.mismatch("bump_map", column, "a name or a file name", &other)),
+                };
+                let strength_column = self.properties.get("bump_strength").map(|p| p.column);
+                let strength = self.optional_number("bump_strength")?.unwrap_or(0.05);
+                if let Some(column) = strength_column.filter(|_| !strength.is_finite()) {
+                    return Err(ParseError::new(
+                        self.line,
+                        column,
+                        "`bump_strength` must be a finite number",
+                    ));
+                }
+                Ok(Some(Bump::Height { texture, strength }))
+            }
+        }
+    }
+
+    // picture that is used as stored, without the sRGB curve
+    fn data_texture(&self, file: &str, column: usize, base_dir: &Path) -> Result<Arc<dyn Texture>, ParseError> {
+        let image = ImageTexture::load_data(&base_dir.join(file)).map_err(|e| {
+            ParseError::new(self.line, column, format!("cannot load \"{file}\": {e}"))
+        })?;
+        Ok(Arc::new(image))
+    }
+
+    // `texture=checker`, `gradient`, `noise` or `turbulence` blend `color` and `second_color`,
+    // `marble` and `wood` have their own default colors, a string loads a PNG or PPM picture
+    fn optional_texture(&mut self, base_dir: &Path) -> Result<Option<Arc<dyn Texture>>, ParseError> {
+        let texture: Arc<dyn Texture> = match self.take("texture") {
+            None => return Ok(None),
+            Some((Value::Str(file), column)) => {
+                let image = ImageTexture::load(&base_dir.join(&file)).map_err(|e| {
+                    ParseError::new(self.line, column, format!("cannot load texture \"{file}\": {e}"))
+                })?;
+                Arc::new(image)
+            }
+            Some((Value::Ident(kind), column)) => {
+                match kind.as_str() {
+                    "checker" => {
+                        let (first, second) = self.texture_colors()?;
+                        let size = self.optional_positive("checker_size")?.unwrap_or(0.1);
+                        let even = Arc::new(SolidColor::new(first));
+                        let odd = Arc::new(SolidColor::new(second));
+                        Arc::new(Checker::new(even, odd, size))
+                    }
+                    "gradient" => {
+                        let (first, second) = self.texture_colors()?;
+                        let direction = match self.optional_ident("direction")? {
+                            None => GradientDirection::V,
+                            Some((name, column)) => match name.as_str() {
+                                "u" => GradientDirection::U,
+                                "v" => GradientDirection::V,
+                                _ => {
+                                    return Err(ParseError::new(
+                                        self.line,
+                                        column,
+                                        format!("unknown direction `{name}`, use `u` or `v`"),
+                                    ))
+                                }
+                            },
+                        };
+                        Arc::new(Gradient::new(first, second, direction))
+                    }
+                    "noise" => {
+                        let (first, second) = self.texture_colors()?;
+                        Arc::new(Noise::new(first, second, self.noise_settings()?))
+                    }
+                    "turbulence" => {
+                        let (first, second) = self.texture_colors()?;
+                        Arc::new(Turbulence::new(first, second, self.noise_settings()?))
+                    }
+                    "marble" => {
+                        let stone = self.optional_color("color")?.unwrap_or(Marble::STONE);
+                        let vein = self.optional_color("second_color")?.unwrap_or(Marble::VEIN);
+                        Arc::new(Marble::new(stone, vein, self.noise_settings()?))
+                    }
+                    "wood" => {
+                        let light = self.optional_color("color")?.unwrap_or(Wood::LIGHT);
+                        let dark = self.optional_color("second_color")?.unwrap_or(Wood::DARK);
+                        Arc::new(Wood::new(light, dark, self.noise_settings()?))
+                    }
+                    _ => {
+                        return Err(ParseError::new(
+                            self.line,
+                            column,
+                            format!("unknown texture `{kind}`"),
+                        ))
+                    }
+                }
+            }
+            Some((other, column)) => {
+                return Err(self.mismatch("texture", column, "a name or a file name", &other))
+            }
+        };
+        Ok(Some(texture))
+    }
+
+    fn texture_colors(&mut self) -> Result<(Color, Color), ParseError> {
+        Ok((self.color("color")?, self.color("second_color")?))
+    }
+
+    // `noise_scale` and `octaves` of the procedural textures
+    fn noise_settings(&mut self) -> Result<NoiseSettings, ParseError> {
+        let defaults = NoiseSettings::default();
+        let scale = self.optional_positive("noise_scale")?.unwrap_or(defaults.scale);
+        let octaves = self.optional_count("octaves")?.unwrap_or(defaults.octaves);
+        Ok(NoiseSettings { scale, octaves })
+    }
+
+    // color defaults to white
+    fn light_emission(&mut self) -> Result<(Color, f64), ParseError> {
+        let color = self.optional_color("color")?.unwrap_or(Color::white());
+        let intensity = self.number("intensity")?;
+        Ok((color, intensity))
+    }
+
+    fn material(&mut self, base_dir: &Path) -> Result<Material, ParseError> {
+        self.optional_material(base_dir)?
+            .ok_or_else(|| self.error(format!("`{}` requires `material`", self.name)))
+    }
+
+    // every property has to be consumed by the directive, otherwise it is a typo
+    fn finish(&self) -> Result<(), ParseError> {
+        let unused = self
+            .properties
+            .iter()
+            .filter(|(_, p)| !p.used)
+            .min_by_key(|(_, p)| p.column);
+        match unused {
+            None => Ok(()),
+            Some((key, p)) => Err(ParseError::new(
+                self.line,
+                p.column,
+                format!("unknown property `{key}` for `{}`", self.name),
+            )),
+        }
+    }
+}
+
+struct Cursor<'t> {
+    tokens: &'t [Spanned],
+    pos: usize,
+    line: usize,
+}
+
+impl<'t> Cursor<'t> {
+    fn done(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn next(&mut self, expected: &str) -> Result<&'t Spanned, ParseError> {
+        match self.tokens.get(self.pos) {
+            Some(spanned) => {
+                self.pos += 1;
+                Ok(spanned)
+            }
+            None => {
+                let column = self
+                    .tokens
+                    .last()
+                    .map(|t| t.column + 1)
+                    .unwrap_or(1);
+                Err(ParseError::new(
+                    self.line,
+                    column,
+                    format!("expected {expected}, found end of line"),
+                ))
+            }
+        }
+    }
+
+    fn unexpected(&self, spanned: &Spanned, expected: &str) -> ParseError {
+        ParseError::new(
+            self.line,
+            spanned.column,
+            format!("expected {expected}, found {}", spanned.token),
+        )
+    }
+
+    fn ident(&mut self) -> Result<(String, usize), ParseError> {
+        let spanned = self.next("a name")?;
+        match &spanned.token {
+            Token::Ident(s) => Ok((s.clone(), spanned.column)),
+            _ => Err(self.unexpected(spanned, "a name")),
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), ParseError> {
+        let expected = token.to_string();
+        let spanned = self.next(&expected)?;
+        if spanned.token != token {
+            return Err(self.unexpected(spanned, &expected));
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> Result<f64, ParseError> {
+        let spanned = self.next("a number")?;
+        match spanned.token {
+            Token::Number(n) => Ok(n),
+            _ => Err(self.unexpected(spanned, "a number")),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, ParseError> {
+        let spanned = self.next("a value")?;
+        match &spanned.token {
+            Token::Number(n) => Ok(Value::Number(*n)),
+            Token::Ident(s) => Ok(Value::Ident(s.clone())),
+            Token::Str(s) => Ok(Value::Str(s.clone())),
+            Token::LParen => {
+                let x = self.number()?;
+                self.expect(Token::Comma)?;
+                let y = self.number()?;
+                self.expect(Token::Comma)?;
+                let z = self.number()?;
+                self.expect(Token::RParen)?;
+                Ok(Value::Vector(Point3D::new(x, y, z)))
+            }
+            _ => Err(self.unexpected(spanned, "a value")),
+        }
+    }
+}
+
+struct ImageSettings {
+    width: usize,
+    height: usize,
+    samples: usize,
+    filter: Filter,
+    sampler: SamplerKind,
+    integrator: IntegratorKind,
+    seed: u64,
+    tone_map: ToneMap,
+}
+
+impl Default for ImageSettings {
+    fn default() -> Self {
+        Self {
+            width: 400,
+            height: 300,
+            samples: 25,
+            filter: Filter::pixel_box(),
+            sampler: SamplerKind::Sobol,
+            integrator: IntegratorKind::Whitted,
+            seed: DEFAULT_SEED,
+            tone_map: ToneMap::default(),
+        }
+    }
+}
+
+struct CameraSettings {
+    origin: Point3D,
+    target: Point3D,
+    up: Point3D,
+    fov: FieldOfView,
+    lens: Lens,
+    projection: Projection,
+}
+
+struct SceneBuilder {
+    base_dir: PathBuf,
+    image: Option<ImageSettings>,
+    camera: Option<CameraSettings>,
+    lights: Vec<LightSource>,
+    objects: Vec<Box<dyn Hittable>>,
+    // materials declared so far
+    materials: usize,
+}
+
+impl SceneBuilder {
+    fn new(base_dir: &Path) -> Self {
+        Self {
+            base_dir: base_dir.to_path_buf(),
+            image: None,
+            camera: None,
+            lights: vec![],
+            objects: vec![],
+            materials: 0,
+        }
+    }
+
+    fn add(&mut self, d: &mut Directive) -> Result<(), ParseError> {
+        match d.name.as_str() {
+            "image" => {
+                if self.image.is_some() {
+                    return Err(d.error("`image` is already defined"));
+                }
+                let defaults = ImageSettings::default();
+                let width = d.optional_count("width")?.unwrap_or(defaults.width);
+                let height = d.optional_count("height")?.unwrap_or(defaults.height);
+                let samples = d.optional_count("samples")?.unwrap_or(defaults.samples);
+                let kind = match d.optional_ident("filter")? {
+                    None => FilterKind::Box,
+                    Some((name, column)) => FilterKind::from_name(&name).ok_or_else(|| {
+                        ParseError::new(d.line, column, format!("unknown filter `{name}`"))
+                    })?,
+                };
+                let radius = d.optional_positive("filter_radius")?.unwrap_or(kind.default_radius());
+                let sampler = match d.optional_ident("sampler")? {
+                    None => defaults.sampler,
+                    Some((name, column)) => SamplerKind::from_name(&name).ok_or_else(|| {
+                        ParseError::new(d.line, column, format!("unknown sampler `{name}`"))
+                    })?,
+                };
+                let integrator = match d.optional_ident("integrator")? {
+                    None => defaults.integrator,
+                    Some((name, column)) => {
+                        let ao_distance = if name == "ambient_occlusion" {
+                            d.optional_positive("ao_distance")?.unwrap_or(DEFAULT_AO_DISTANCE)
+                        } else {
+                            DEFAULT_AO_DISTANCE
+                        };
+                        IntegratorKind::from_name(&name, ao_distance).ok_or_else(|| {
+                            ParseError::new(d.line, column, format!("unknown integrator `{name}`"))
+                        })?
+                    }
+                };
+                let seed = d.optional_seed("seed")?.unwrap_or(defaults.seed);
+                let operator = match d.optional_ident("tone_map")? {
+                    None => defaults.tone_map.operator,
+                    Some((name, column)) => {
+                        // luminance that becomes pure white
+                        let white = if name == "extended_reinhard" {
+                            d.optional_positive("white")?.unwrap_or(4.)
+                        } else {
+                            1.
+                        };
+                        Operator::from_name(&name, white).ok_or_else(|| {
+                            ParseError::new(d.line, column, format!("unknown tone map `{name}`"))
+                        })?
+                    }
+                };
+                let exposure = d.optional_number("exposure")?.unwrap_or(defaults.tone_map.exposure);
+                self.image = Some(ImageSettings {
+                    width,
+                    height,
+                    samples,
+                    filter: Filter::new(kind, radius),
+                    sampler,
+                    integrator,
+                    seed,
+                    tone_map: ToneMap::new(operator, exposure),
+                });
+            }
+            "camera" => {
+                if self.camera.is_some() {
+                    return Err(d.error("`camera` is already defined"));
+                }
+                let origin = d.point("origin")?;
+                let target = d.point("target")?;
+                let up = d.point("up")?;
+                let projection = match d.optional_ident("projection")? {
+                    None => Projection::Perspective,
+                    Some((name, column)) => match name.as_str() {
+                        "perspective" => Projection::Perspective,
+                        "orthographic" => Projection::Orthographic {
+                            height: d.number("view_height")?,
+                        },
+                        "fisheye" => Projection::Fisheye,
+                        "equirectangular" => Projection::Equirectangular,
+                        _ => {
+                            return Err(ParseError::new(
+                                d.line,
+                                column,
+                                format!("unknown projection `{name}`"),
+                            ))
+                        }
+                    },
+                };
+                // orthographic and panoramic views have a fixed extent
+                let fov = match projection {
+                    Projection::Perspective => d.field_of_view(180.)?,
+                    Projection::Fisheye => d.field_of_view(360.)?,
+                    _ => FieldOfView::Vertical(0.),
+                };
+                let mut lens = Lens::pinhole();
+                if let Some(aperture) = d.optional_non_negative("aperture")? {
+                    lens.aperture = aperture;
+                }
+                if let Some(focus) = d.optional_focus("focus_distance")? {
+                    lens.focus = focus;
+                }
+                self.camera = Some(CameraSettings {
+                    origin,
+                    target,
+                    up,
+                    fov,
+                    lens,
+                    projection,
+                });
+            }
+            "point_light" => {
+                let position = d.point("position")?;
+                let (color, intensity) = d.light_emission()?;
+                let light = PointLight::new(position, color, intensity);
+                self.lights.push(LightSource::Point(light));
+            }
+            "directional_light" => {
+                let direction = d.direction("direction")?;
+                let (color, intensity) = d.light_emission()?;
+                let light = DirectionalLight::new(direction, color, intensity);
+                self.lights.push(LightSource::Directional(light));
+            }
+            "spot_light" => {
+                let position = d.point("position")?;
+                let direction = d.direction("direction")?;
+                let outer_column = d.properties.get("angle").map(|p| p.column);
+                let outer_angle = d.number("angle")?;
+                if let Some(column) = outer_column.filter(|_| !(outer_angle > 0. && outer_angle < 180.)) {
+                    return Err(ParseError::new(
+                        d.line,
+                        column,
+                        "`angle` must be between 0 and 180 degrees",
+                    ));
+                }
+                let inner_column = d.properties.get("inner_angle").map(|p| p.column);
+                let inner_angle = d.optional_non_negative("inner_angle")?.unwrap_or(outer_angle * 0.75);
+                if let Some(column) = inner_column.filter(|_| inner_angle > outer_angle) {
+                    return Err(ParseError::new(
+                        d.line,
+                        column,
+                        "`inner_angle` must not be larger than `angle`",
+                    ));
+                }
+                let (color, intensity) = d.light_emission()?;
+                let light = SpotLight::new(
+                    position,
+                    direction,
+                    inner_angle.to_radians(),
+                    outer_angle.to_radians(),
+                    color,
+                    intensity,
+                );
+                self.lights.push(LightSource::Spot(light));
+            }
+            "sphere_light" => {
+                let center = d.point("center")?;
+                let radius = d.optional_positive("radius")?.unwrap_or(1.);
+                let samples = d.optional_count("samples")?.unwrap_or(4);
+                let (color, intensity) = d.light_emission()?;
+                let light = SphereLight::new(center, radius, samples, color, intensity);
+                self.lights.push(LightSource::Sphere(light));
+            }
+            "sphere" => {
+                let center = d.point("center")?;
+                let radius = d.positive("radius")?;
+                let material = d.material(&self.base_dir)?.with_id(self.next_material_id());
+                self.add_object(d, Box::new(Sphere::new(center, radius, material)))?;
+            }
+            "cube" => {
+                let min = d.point("min")?;
+                let max = d.point("max")?;
+                let material = d.material(&self.base_dir)?.with_id(self.next_material_id());
+                self.add_object(d, Box::new(Cube::new(min, max, material)))?;
+            }
+            "plane" => {
+                let point = d.point("point")?;
+                let normal = d.direction("normal")?;
+                let plane = match d.optional_material(&self.base_dir)? {
+                    Some(material) => {
+                        Plane::with_material(point, normal, material.with_id(self.next_material_id()))
+                    }
+                    None => Plane::new(point, normal),
+                };
+                self.add_object(d, Box::new(plane))?;
+            }
+            "cylinder" => {
+                let base = d.point("base")?;
+                let top = d.top(base)?;
+                let radius = d.positive("radius")?;
+                let material = d.material(&self.base_dir)?.with_id(self.next_material_id());
+                self.add_object(d, Box::new(Cylinder::between(base, top, radius, material)))?;
+            }
+            "cone" => {
+                let base = d.point("base")?;
+                let top = d.top(base)?;
+                let radius_column = d.properties.get("radius").map(|p| p.column);
+                let radius = d.non_negative("radius")?;
+                let top_radius = d.optional_non_negative("top_radius")?.unwrap_or(0.);
+                if let Some(column) = radius_column.filter(|_| radius == 0. && top_radius == 0.) {
+                    return Err(ParseError::new(
+                        d.line,
+                        column,
+                        "`radius` or `top_radius` must be positive",
+                    ));
+                }
+                let material = d.material(&self.base_dir)?.with_id(self.next_material_id());
+                let cone = Cone::new(base, top, radius, top_radius, material);
+                self.add_object(d, Box::new(cone))?;
+            }
+            "triangle" => {
+                let a = d.point("a")?;
+                let b = d.point("b")?;
+                let c = d.point("c")?;
+                let material = d.material(&self.base_dir)?.with_id(self.next_material_id());
+                self.add_object(d, Box::new(Triangle::new(a, b, c, material)))?;
+            }
+            "mesh" => {
+                let (file, column) = d.string("file")?;
+                let material = d.material(&self.base_dir)?.with_id(self.next_material_id());
+                let mesh = Mesh::load(&self.base_dir.join(&file), material).map_err(|e| {
+                    ParseError::new(d.line, column, format!("cannot load mesh \"{file}\": {e}"))
+                })?;
+                self.add_object(d, Box::new(mesh))?;
+            }
+            name => return Err(d.error(format!("unknown directive `{name}`"))),
+        }
+        Ok(())
+    }
+
+    // materials are numbered from 1 in the order they are declared, 0 is the default plane material
+    fn next_material_id(&mut self) -> usize {
+        self.materials += 1;
+        self.materials
+    }
+
+    // objects can be scaled, then rotated around the x, y and z axis, then translated
+    fn add_object(&mut self, d: &mut Directive, object: Box<dyn Hittable>) -> Result<(), ParseError> {
+        let Some(transform) = d.optional_transform()? else {
+            self.objects.push(object);
+            return Ok(());
+        };
+        let transformed = Transformed::new(object, transform)
+            .ok_or_else(|| d.error("`scale` must not be 0"))?;
+        self.objects.push(Box::new(transformed));
+        Ok(())
+    }
+
+    // `end` is the line just past the last one, used for errors about missing directives
+    fn build(self, end: usize) -> Result<Config, ParseError> {
+        let image = self.image.unwrap_or_default();
+        let camera = self
+            .camera
+            .ok_or_else(|| ParseError::new(end, 1, "scene has no `camera`"))?;
+
+        let aspect_ratio = image.width as f64 / image.height as f64;
+
+        Ok(Config {
+            image: Image::new(
+                image.width,
+                image.height,
+                image.samples as i32,
+                image.filter,
+                image.tone_map,
+            ),
+            sampler: image.sampler,
+            integrator: image.integrator,
+            seed: image.seed,
+            camera: Camera::new(
+                camera.origin,
+                camera.target,
+                camera.up,
+                camera.fov,
+                aspect_ratio,
+                camera.lens,
+                camera.projection,
+            ),
+            lights: self.lights,
+            objects: self.objects,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAMERA: &str = "camera origin=(0, 2, 0) target=(0, 0, -4) up=(0, 1, 0) fov=50\n";
+
+    fn error(source: &str) -> String {
+        match parse(source, Path::new("")) {
+            Ok(_) => panic!("`{source}` parsed without an error"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    fn transform(line: &str) -> Matrix4 {
+        let tokens = tokenize(line, 1).unwrap();
+        let mut directive = Directive::parse(&tokens, 1).unwrap();
+        directive.optional_transform().unwrap().unwrap()
+    }
+
+    fn assert_close(actual: Point3D, expected: Point3D) {
+        assert!((actual - expected).length() < 1e-9, "{actual:?} != {expected:?}");
+    }
+
+    #[test]
+    fn valid_scene_parses() {
+        let source = format!("{CAMERA}sphere center=(0, 0, -1) radius=1 material=matte color=red");
+        let config = parse(&source, Path::new("")).unwrap_or_else(|e| panic!("{e}"));
+        assert_eq!(config.objects.len(), 1);
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_token() {
+        assert_eq!(
+            error("sphere center=(0, 0 -1) radius=1 material=matte color=red"),
+            "1:21: expected `,`, found `-1`"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=matte color=red $"),
+            "1:60: unexpected character `$`"
+        );
+    }
+
+    #[test]
+    fn unknown_names_are_reported() {
+        assert_eq!(error("box min=(0, 0, 0)"), "1:1: unknown directive `box`");
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=matte color=red colour=blue"),
+            "1:60: unknown property `colour` for `sphere`"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=plastic"),
+            "1:35: unknown material `plastic`"
+        );
+        assert_eq!(error("image integrator=magic"), "1:7: unknown integrator `magic`");
+    }
+
+    #[test]
+    fn invalid_values_point_at_the_property() {
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=(1, 2, 3) material=matte color=red"),
+            "1:26: `radius` must be a number, found a vector"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=-1 material=matte color=red"),
+            "1:26: `radius` must be positive"
+        );
+        assert_eq!(
+            error("sphere_light center=(0, 4, -3) radius=0 intensity=10"),
+            "1:32: `radius` must be positive"
+        );
+        assert_eq!(error("image width=0"), "1:7: `width` must be a positive whole number");
+        assert_eq!(
+            error("camera origin=(0, 2, 0) target=(0, 0, -4) up=(0, 1, 0) fov=50 aperture=-1"),
+            "1:63: `aperture` must not be negative"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=dielectric ior=0"),
+            "1:55: `ior` must be positive"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=metal roughness=5"),
+            "1:50: `roughness` must be between 0 and 1"
+        );
+        assert_eq!(
+            error("plane point=(0, 0, 0) normal=(0, 1, 0) material=matte texture=checker color=red second_color=white checker_size=0"),
+            "1:100: `checker_size` must be positive"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=matte texture=marble noise_scale=-2"),
+            "1:65: `noise_scale` must be positive"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=matte color=red bump_map=noise bump_scale=0"),
+            "1:75: `bump_scale` must be positive"
+        );
+        assert_eq!(error("image filter=gaussian filter_radius=0"), "1:23: `filter_radius` must be positive");
+        assert_eq!(error("image integrator=ambient_occlusion ao_distance=-1"), "1:36: `ao_distance` must be positive");
+        assert_eq!(error("image tone_map=extended_reinhard white=0"), "1:34: `white` must be positive");
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=matte color=red bump_map=noise bump_strength=1e400"),
+            "1:75: `bump_strength` must be a finite number"
+        );
+        assert_eq!(
+            error("spot_light position=(0, 4, -3) direction=(0, -1, 0) angle=20 inner_angle=30 intensity=10"),
+            "1:62: `inner_angle` must not be larger than `angle`"
+        );
+        assert_eq!(
+            error("spot_light position=(0, 4, -3) direction=(0, -1, 0) angle=180 intensity=10"),
+            "1:53: `angle` must be between 0 and 180 degrees"
+        );
+        assert_eq!(
+            error("spot_light position=(0, 4, -3) direction=(0, -1, 0) angle=20 inner_angle=-5 intensity=10"),
+            "1:62: `inner_angle` must not be negative"
+        );
+        assert_eq!(
+            error("directional_light direction=(0, 0, 0) intensity=2"),
+            "1:19: `direction` must not be 0"
+        );
+        assert_eq!(
+            error("spot_light position=(0, 4, -3) direction=(0, 0, 0) angle=20 intensity=10"),
+            "1:32: `direction` must not be 0"
+        );
+        assert_eq!(
+            error("cone base=(0, 0, 0) height=1 radius=0 top_radius=0 material=matte color=red"),
+            "1:30: `radius` or `top_radius` must be positive"
+        );
+        assert_eq!(error("plane point=(0, 0, 0) normal=(0, 0, 0)"), "1:23: `normal` must not be 0");
+    }
+
+    #[test]
+    fn structural_errors_are_reported() {
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 radius=2"),
+            "1:35: duplicate property `radius`"
+        );
+        assert_eq!(
+            error("sphere center=(0, 0, -1) radius=1 material=matte"),
+            "1:1: `sphere` requires `color`"
+        );
+        assert_eq!(error(&format!("{CAMERA}{CAMERA}")), "2:1: `camera` is already defined");
+        assert_eq!(error("\n"), "2:1: scene has no `camera`");
+    }
+
+    #[test]
+    fn transforms_scale_then_rotate_then_translate() {
+        let m = transform("sphere scale=(2, 1, 1) rotate=(0, 0, 90) translate=(1, 0, 0)");
+        // (2, 0, 0) after scaling, (0, 2, 0) after rotating
+        assert_close(m.transform_point(&Point3D::new(1., 0., 0.)), Point3D::new(1., 2., 0.));
+    }
+
+    #[test]
+    fn rotations_apply_x_then_y_then_z() {
+        let m = transform("sphere rotate=(90, 90, 0)");
+        // (0, 0, 1) after rotating around x, (1, 0, 0) after rotating around y
+        assert_close(m.transform_point(&Point3D::new(0., 1., 0.)), Point3D::new(1., 0., 0.));
+        let m = transform("sphere rotate=(0, 90, 90)");
+        // (0, 0, -1) after rotating around y, unchanged around z
+        assert_close(m.transform_point(&Point3D::new(1., 0., 0.)), Point3D::new(0., 0., -1.));
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{color::Color, image::load_rgb8, noise::Perlin, point3d::Point3D};
+use std::{f64::consts::PI, io, path::Path, sync::Arc};
+
+// Color that varies over a surface. `uv` are the surface coordinates of the
+// hit, `point` its position for textures that fill space instead.
+pub trait Texture: Send + Sync {
+    fn value(&self, uv: (f64, f64), point: &Point3D) -> Color;
+}
+
+// The same color everywhere
+pub struct SolidColor {
+    color: Color,
+}
+
+impl SolidColor {
+    pub fn new(color: Color) -> Self {
+        Self { color }
+    }
+}
+
+impl Texture for SolidColor {
+    fn value(&self, _uv: (f64, f64), _point: &Point3D) -> Color {
+        self.color
+    }
+}
+
+// Squares of two alternating textures, `size` is the width of a square in uv units
+pub struct Checker {
+    even: Arc<dyn Texture>,
+    odd: Arc<dyn Texture>,
+    size: f64,
+}
+
+impl Checker {
+    pub fn new(even: Arc<dyn Texture>, odd: Arc<dyn Texture>, size: f64) -> Self {
+        Self { even, odd, size }
+    }
+}
+
+impl Texture for Checker {
+    fn value(&self, uv: (f64, f64), point: &Point3D) -> Color {
+        let column = (uv.0 / self.size).floor() as i64;
+        let row = (uv.1 / self.size).floor() as i64;
+        if (column + row) % 2 == 0 {
+            self.even.value(uv, point)
+        } else {
+            self.odd.value(uv, point)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GradientDirection {
+    U,
+    V,
+}
+
+// Blends linearly from one color at 0 to the other at 1, repeats outside of that range
+pub struct Gradient {
+    from: Color,
+    to: Color,
+    direction: GradientDirection,
+}
+
+impl Gradient {
+    pub fn new(from: Color, to: Color, direction: GradientDirection) -> Self {
+        Self {
+            from,
+            to,
+            direction,
+        }
+    }
+}
+
+impl Texture for Gradient {
+    fn value(&self, uv: (f64, f64), _point: &Point3D) -> Color {
+        let x = match self.direction {
+            GradientDirection::U => uv.0,
+            GradientDirection::V => uv.1,
+        };
+        mix(self.from, self.to, x - x.floor())
+    }
+}
+
+// The noise textures below fill space, they color the surface by the hit point
+// instead of its uv. `scale` is how many noise features fit into one scene unit,
+// every octave adds finer detail.
+pub struct NoiseSettings {
+    pub scale: f64,
+    pub octaves: usize,
+}
+
+impl Default for NoiseSettings {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            octaves: 6,
+        }
+    }
+}
+
+// Soft, cloudy blend of two colors from fractal Brownian motion
+pub struct Noise {
+    first: Color,
+    second: Color,
+    settings: NoiseSettings,
+    perlin: Perlin,
+}
+
+impl Noise {
+    pub fn new(first: Color, second: Color, settings: NoiseSettings) -> Self {
+        Self {
+            first,
+            second,
+            settings,
+            perlin: Perlin::default(),
+        }
+    }
+}
+
+impl Texture for Noise {
+    fn value(&self, _uv: (f64, f64), point: &Point3D) -> Color {
+        let p = *point * self.settings.scale;
+        let t = 0.5 + 0.5 * self.perlin.fbm(&p, self.settings.octaves);
+        mix(self.first, self.second, t.clamp(0., 1.))
+    }
+}
+
+// Turbulence has sharp dark creases, the look of fire, smoke or lava
+pub struct Turbulence {
+    first: Color,
+    second: Color,
+    settings: NoiseSettings,
+    perlin: Perlin,
+}
+
+impl Turbulence {
+    pub fn new(first: Color, second: Color, settings: NoiseSettings) -> Self {
+        Self {
+            first,
+            second,
+            settings,
+            perlin: Perlin::default(),
+        }
+    }
+}
+
+impl Texture for Turbulence {
+    fn value(&self, _uv: (f64, f64), point: &Point3D) -> Color {
+        let p = *point * self.settings.scale;
+        let t = self.perlin.turbulence(&p, self.settings.octaves);
+        mix(self.first, self.second, t.clamp(0., 1.))
+    }
+}
+
+// Stripes along x bent by turbulence into veins
+pub struct Marble {
+    stone: Color,
+    vein: Color,
+    settings: NoiseSettings,
+    perlin: Perlin,
+}
+
+impl Marble {
+    pub const STONE: Color = Color::new(0.9, 0.89, 0.86);
+    pub const VEIN: Color = Color::new(0.25, 0.25, 0.3);
+
+    pub fn new(stone: Color, vein: Color, settings: NoiseSettings) -> Self {
+        Self {
+            stone,
+            vein,
+            settings,
+            perlin: Perlin::default(),
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, _uv: (f64, f64), point: &Point3D) -> Color {
+        let p = *point * self.settings.scale;
+        let phase = p.x() + 2. * self.perlin.turbulence(&p, self.settings.octaves);
+        // thin veins where the sine wave peaks
+        let t = (0.5 + 0.5 * (phase * PI).sin()).powi(8);
+        mix(self.stone, self.vein, t)
+    }
+}
+
+// Growth rings around the y axis, made irregular by noise
+pub struct Wood {
+    light: Color,
+    dark: Color,
+    settings: NoiseSettings,
+    perlin: Perlin,
+}
+
+impl Wood {
+    pub const LIGHT: Color = Color::new(0.75, 0.53, 0.3);
+    pub const DARK: Color = Color::new(0.42, 0.24, 0.11);
+
+    pub fn new(light: Color, dark: Color, settings: NoiseSettings) -> Self {
+        Self {
+            light,
+            dark,
+            settings,
+            perlin: Perlin::default(),
+        }
+    }
+}
+
+impl Texture for Wood {
+    fn value(&self, _uv: (f64, f64), point: &Point3D) -> Color {
+        let p = *point * self.settings.scale;
+        let radius = (p.x() * p.x() + p.z() * p.z()).sqrt();
+        let rings = 4. * radius + 0.5 * self.perlin.fbm(&p, self.settings.octaves);
+        // each ring darkens gradually and ends in a sharp edge
+        let t = rings - rings.floor();
+        mix(self.light, self.dark, t)
+    }
+}
+
+// Picture wrapped around the surface. It repeats outside of uv range 0 - 1,
+// v = 0 is the bottom row of the picture.
+pub struct ImageTexture {
+    width: usize,
+    height: usize,
+    // linear colors, rows top to bottom
+    pixels: Vec<Color>,
+}
+
+impl ImageTexture {
+    // PNG or PPM file with sRGB colors
+    pub fn load(path: &Path) -> io::Result<Self> {
+        ImageTexture::load_with(path, srgb_to_linear)
+    }
+    // pictures that hold data instead of colors, e.g. normal or height maps, are used as stored
+    pub fn load_data(path: &Path) -> io::Result<Self> {
+        ImageTexture::load_with(path, |value| value as f64 / 255.)
+    }
+    fn load_with(path: &Path, decode: fn(u8) -> f64) -> io::Result<Self> {
+        let (width, height, rgb) = load_rgb8(path)?;
+        if width == 0 || height == 0 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "image is empty"));
+        }
+        let pixels = rgb
+            .chunks(3)
+            .map(|p| Color::new(decode(p[0]), decode(p[1]), decode(p[2])))
+            .collect();
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+    fn pixel(&self, x: i64, y: i64) -> Color {
+        let x = x.rem_euclid(self.width as i64) as usize;
+        let y = y.rem_euclid(self.height as i64) as usize;
+        self.pixels[x + y * self.width]
+    }
+}
+
+impl Texture for ImageTexture {
+    // bilinear interpolation between the four nearest pixel centers
+    fn value(&self, uv: (f64, f64), _point: &Point3D) -> Color {
+        let x = uv.0 * self.width as f64 - 0.5;
+        let y = (1. - uv.1) * self.height as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+        let top = self.pixel(x0, y0) * (1. - fx) + self.pixel(x0 + 1, y0) * fx;
+        let bottom = self.pixel(x0, y0 + 1) * (1. - fx) + self.pixel(x0 + 1, y0 + 1) * fx;
+        top * (1. - fy) + bottom * fy
+    }
+}
+
+fn mix(a: Color, b: Color, t: f64) -> Color {
+    a * (1. - t) + b * t
+}
+
+// inverse of the sRGB transfer curve
+fn srgb_to_linear(value: u8) -> f64 {
+    let c = value as f64 / 255.;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
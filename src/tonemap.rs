@@ -0,0 +1,106 @@
+use crate::color::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    // linear, everything brighter than white is clipped
+    Clamp,
+    // compresses the luminance with L / (1 + L), never reaches white
+    Reinhard,
+    // Reinhard that maps the luminance `white` to exactly white
+    ExtendedReinhard { white: f64 },
+    // Narkowicz's fit of the ACES filmic curve
+    Aces,
+    // Hable's filmic curve from Uncharted 2
+    Uncharted2,
+}
+
+impl Operator {
+    // `white` is only used by extended Reinhard
+    pub fn from_name(name: &str, white: f64) -> Option<Self> {
+        match name {
+            "clamp" => Some(Operator::Clamp),
+            "reinhard" => Some(Operator::Reinhard),
+            "extended_reinhard" => Some(Operator::ExtendedReinhard { white }),
+            "aces" => Some(Operator::Aces),
+            "uncharted2" => Some(Operator::Uncharted2),
+            _ => None,
+        }
+    }
+}
+
+// Turns linear radiance into display values: exposure, then the operator,
+// then the sRGB transfer curve
+#[derive(Debug, Clone, Copy)]
+pub struct ToneMap {
+    pub operator: Operator,
+    // in stops, every +1 doubles the brightness
+    pub exposure: f64,
+}
+
+impl ToneMap {
+    pub fn new(operator: Operator, exposure: f64) -> Self {
+        Self { operator, exposure }
+    }
+    pub fn to_rgb8(&self, color: Color) -> [u8; 3] {
+        let scale = 2f64.powf(self.exposure);
+        let [r, g, b] = color.components().map(|c| (c * scale).max(0.));
+        let mapped = match self.operator {
+            Operator::Clamp => [r, g, b],
+            Operator::Reinhard => scale_luminance([r, g, b], |l| l / (1. + l)),
+            Operator::ExtendedReinhard { white } => {
+                scale_luminance([r, g, b], |l| l * (1. + l / (white * white)) / (1. + l))
+            }
+            Operator::Aces => [r, g, b].map(aces),
+            Operator::Uncharted2 => {
+                const WHITE: f64 = 11.2;
+                const EXPOSURE_BIAS: f64 = 2.;
+                [r, g, b].map(|c| uncharted2(c * EXPOSURE_BIAS) / uncharted2(WHITE))
+            }
+        };
+        mapped.map(|c| (srgb(c.clamp(0., 1.)) * 255. + 0.5) as u8)
+    }
+}
+
+impl Default for ToneMap {
+    fn default() -> Self {
+        ToneMap::new(Operator::Clamp, 0.)
+    }
+}
+
+// applies `curve` to the luminance and keeps the hue
+fn scale_luminance(rgb: [f64; 3], curve: impl Fn(f64) -> f64) -> [f64; 3] {
+    let luminance = Color::new(rgb[0], rgb[1], rgb[2]).luminance();
+    if luminance <= 0. {
+        return rgb;
+    }
+    let scale = curve(luminance) / luminance;
+    rgb.map(|c| c * scale)
+}
+
+fn aces(x: f64) -> f64 {
+    const A: f64 = 2.51;
+    const B: f64 = 0.03;
+    const C: f64 = 2.43;
+    const D: f64 = 0.59;
+    const E: f64 = 0.14;
+    (x * (A * x + B)) / (x * (C * x + D) + E)
+}
+
+fn uncharted2(x: f64) -> f64 {
+    const A: f64 = 0.15;
+    const B: f64 = 0.50;
+    const C: f64 = 0.10;
+    const D: f64 = 0.20;
+    const E: f64 = 0.02;
+    const F: f64 = 0.30;
+    ((x * (A * x + C * B) + D * E) / (x * (A * x + B) + D * F)) - E / F
+}
+
+// linear value in range 0 - 1 to the sRGB transfer curve
+fn srgb(c: f64) -> f64 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1. / 2.4) - 0.055
+    }
+}
@@ -0,0 +1,184 @@
+// Renders every scene in `scenes/` at a low resolution and compares it with the
+// reference image in `tests/golden/`. Run with `UPDATE_GOLDEN=1` to accept the
+// current renders as the new references after an intended change.
+
+use rt_test::{
+    config::Config,
+    image::{ppm, Image},
+    raytracer::RayTracer,
+};
+use std::{env, fs, path::Path};
+
+const WIDTH: usize = 80;
+const SAMPLES: i32 = 16;
+const SEED: u64 = 1;
+// root mean square difference of the 8-bit channels, allows for small
+// floating point differences between platforms
+const MAX_RMSE: f64 = 2.;
+
+fn render(scene: &str) -> (usize, usize, Vec<u8>) {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let path = root.join("scenes").join(format!("{scene}.rt"));
+    let source = fs::read_to_string(&path).unwrap();
+    let mut config = Config::parse(&source, path.parent().unwrap())
+        .unwrap_or_else(|e| panic!("{}:{e}", path.display()));
+
+    // same aspect ratio as the scene, so the camera keeps its framing
+    let image = &config.image;
+    let height = (WIDTH * image.height + image.width / 2) / image.width;
+    config.image = Image::new(WIDTH, height, SAMPLES, image.filter, image.tone_map);
+    config.seed = SEED;
+
+    let mut ray_tracer = RayTracer::new(config);
+    ray_tracer.render();
+    (WIDTH, height, ray_tracer.image.to_rgb8())
+}
+
+fn rmse(a: &[u8], b: &[u8]) -> f64 {
+    let sum: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(&x, &y)| (x as f64 - y as f64).powi(2))
+        .sum();
+    (sum / a.len() as f64).sqrt()
+}
+
+fn check(scene: &str) {
+    let (width, height, actual) = render(scene);
+    let reference_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{scene}.ppm"));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&reference_path, ppm::encode(width, height, &actual)).unwrap();
+        return;
+    }
+
+    let bytes = fs::read(&reference_path).unwrap_or_else(|e| {
+        panic!("cannot read {}: {e}, run with UPDATE_GOLDEN=1 to create it", reference_path.display())
+    });
+    let (ref_width, ref_height, expected) = ppm::decode(&bytes).unwrap();
+    let error = if (ref_width, ref_height) == (width, height) {
+        rmse(&actual, &expected)
+    } else {
+        f64::INFINITY
+    };
+    if error <= MAX_RMSE {
+        return;
+    }
+
+    // keep the render and an amplified difference around for inspection
+    let out_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    fs::create_dir_all(&out_dir).unwrap();
+    let actual_path = out_dir.join(format!("{scene}.actual.ppm"));
+    fs::write(&actual_path, ppm::encode(width, height, &actual)).unwrap();
+    let mut message = format!(
+        "{scene} differs from its reference: rmse {error:.3} > {MAX_RMSE}, render written to {}",
+        actual_path.display()
+    );
+    if error.is_finite() {
+        let diff: Vec<u8> = actual
+            .iter()
+            .zip(&expected)
+            .map(|(&x, &y)| (x.abs_diff(y) as u32 * 8).min(255) as u8)
+            .collect();
+        let diff_path = out_dir.join(format!("{scene}.diff.ppm"));
+        fs::write(&diff_path, ppm::encode(width, height, &diff)).unwrap();
+        message += &format!(", difference in {}", diff_path.display());
+    }
+    panic!("{message}");
+}
+
+#[test]
+fn scene_one() {
+    check("scene_one");
+}
+
+#[test]
+fn scene_two() {
+    check("scene_two");
+}
+
+#[test]
+fn scene_three() {
+    check("scene_three");
+}
+
+#[test]
+fn scene_four() {
+    check("scene_four");
+}
+
+#[test]
+fn ambient_occlusion() {
+    check("ambient_occlusion");
+}
+
+#[test]
+fn bump() {
+    check("bump");
+}
+
+#[test]
+fn cones() {
+    check("cones");
+}
+
+#[test]
+fn depth_of_field() {
+    check("depth_of_field");
+}
+
+#[test]
+fn fisheye() {
+    check("fisheye");
+}
+
+#[test]
+fn glass() {
+    check("glass");
+}
+
+#[test]
+fn lights() {
+    check("lights");
+}
+
+#[test]
+fn mesh() {
+    check("mesh");
+}
+
+#[test]
+fn metals() {
+    check("metals");
+}
+
+#[test]
+fn noise() {
+    check("noise");
+}
+
+#[test]
+fn panorama() {
+    check("panorama");
+}
+
+#[test]
+fn path_tracing() {
+    check("path_tracing");
+}
+
+#[test]
+fn soft_shadows() {
+    check("soft_shadows");
+}
+
+#[test]
+fn textures() {
+    check("textures");
+}
+
+#[test]
+fn transforms() {
+    check("transforms");
+}
@@ -0,0 +1,37 @@
+// The image must not depend on how many threads render it or in which order
+// the tiles finish, down to the last bit of every pixel.
+
+use rt_test::{
+    config::Config,
+    filter::{Filter, FilterKind},
+    image::Image,
+    raytracer::RayTracer,
+};
+use std::{fs, path::Path};
+
+fn render(threads: usize) -> Vec<[u64; 3]> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/soft_shadows.rt");
+    let source = fs::read_to_string(&path).unwrap();
+    let mut config = Config::parse(&source, path.parent().unwrap()).unwrap();
+    // several tiles wide and high, with a filter wide enough to cross the tile edges
+    let filter = Filter::new(FilterKind::Mitchell, 2.);
+    config.image = Image::new(72, 40, 4, filter, config.image.tone_map);
+
+    let mut ray_tracer = RayTracer::new(config);
+    ray_tracer.set_threads(threads);
+    ray_tracer.render();
+    ray_tracer
+        .image
+        .to_linear()
+        .iter()
+        .map(|pixel| pixel.components().map(f64::to_bits))
+        .collect()
+}
+
+#[test]
+fn thread_count_does_not_change_the_image() {
+    let single = render(1);
+    for threads in [2, 3, 8] {
+        assert!(render(threads) == single, "{threads} threads render a different image");
+    }
+}